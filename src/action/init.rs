@@ -1,32 +1,40 @@
 use std::collections::HashMap;
 use std::env;
 use std::fs;
+use std::path::PathBuf;
 use std::process::exit;
 
 use crate::action::Action;
 use crate::cli::input;
-use crate::config;
+use crate::config::{self, RepositoryOptions, ScriptPolicy};
 use crate::context;
 use crate::git;
-use crate::meta::TemplateType;
+use crate::error::RunError;
+use crate::meta::{self, Script, TemplateType, Value, ValueType};
 use crate::out;
 use crate::renderer;
-use crate::repository::CopyOptions;
+use crate::repository::{self, CopyOptions, Repository};
+use crate::template::Template;
 use crate::utils;
 
 use clap::ArgMatches;
-use fs_extra::dir;
 
 impl Action {
   pub fn init(&self, args: &ArgMatches) {
     let mut ctx = context::Context::new(args);
 
     ctx.set_no_script(args.get_flag("no_script"));
+    ctx.set_strict(args.get_flag("strict"));
+    ctx.set_quiet(args.get_flag("quiet"));
+    ctx.set_script_timeout(args.get_one::<u64>("script_timeout").copied());
 
     // Parse arguments
     let workspace_name = args.get_one::<String>("name");
     let repository_name = args.get_one::<String>("repository");
-    let template_name = args.get_one::<String>("template");
+    let mut template_names: Vec<String> = match args.get_many::<String>("template") {
+      Some(names) => names.cloned().collect(),
+      None => Vec::new(),
+    };
     let workspace_directory = args.get_one::<String>("directory");
 
     out::info::initiate_workspace();
@@ -51,44 +59,78 @@ impl Action {
       utils::lowercase(workspace_name.unwrap())
     };
 
-    // Get repository
-    let repository = match self.get_repository(repository_name) {
-      Ok(repository) => repository,
-      Err(error) => {
-        log::error!("{}", error);
-        eprintln!("{}", error);
-        exit(1)
-      }
-    };
+    // Templates referenced as repository:template are loaded from their repository,
+    // all others from the given or selected repository
+    let mut repositories: Vec<(Option<String>, Box<dyn Repository>)> = Vec::new();
+    if template_names.is_empty() || template_names.iter().any(|name| repository::parse_reference(name).0.is_none()) {
+      let repository = match self.get_repository(repository_name) {
+        Ok(repository) => repository,
+        Err(error) => {
+          log::error!("{}", error);
+          eprintln!("{}", error);
+          exit(1)
+        }
+      };
 
-    // Check if templates exist
-    let templates = repository.get_template_names();
-    if templates.len() <= 0 {
-      out::error::no_templates(&repository.get_config().name);
-      exit(1);
+      repositories.push((None, repository));
     }
 
-    let template_name = if template_name.is_none() {
+    if template_names.is_empty() {
+      // Check if templates exist
+      let repository = &repositories[0].1;
+      let templates = repository.get_template_names();
+      if templates.is_empty() {
+        out::error::no_templates(&repository.get_config().name);
+        exit(1);
+      }
+
       match input::select("template", &templates) {
-        Ok(value) => value,
+        Ok(value) => template_names.push(value),
         Err(error) => {
           log::error!("{}", error);
           eprintln!("{}", error);
           exit(1);
         }
       }
-    } else {
-      String::from(template_name.unwrap())
-    };
+    }
 
-    let template = match repository.get_template_by_name(&template_name) {
-      Ok(template) => template,
-      Err(error) => {
-        log::error!("{}", error);
-        out::error::template_not_found();
-        exit(1);
-      }
-    };
+    let mut selections: Vec<Selection> = Vec::new();
+    for reference in template_names.iter() {
+      let (repository_name, template_name) = repository::parse_reference(reference);
+      let repository_name = repository_name.map(utils::lowercase);
+
+      // Every repository is only loaded once
+      let index = match repositories.iter().position(|(name, _)| *name == repository_name) {
+        Some(index) => index,
+        None => match crate::repository::load(&self.config, repository_name.as_deref().unwrap_or_default()) {
+          Ok(repository) => {
+            repositories.push((repository_name, repository));
+            repositories.len() - 1
+          }
+          Err(error) => {
+            log::error!("{}", error);
+            eprintln!("{}", error);
+            exit(1);
+          }
+        },
+      };
+
+      let template = match repositories[index].1.get_template_by_name(template_name) {
+        Ok(template) => template.to_owned(),
+        Err(error) => {
+          log::error!("{}", error);
+          out::error::template_not_found();
+          exit(1);
+        }
+      };
+
+      selections.push(Selection {
+        repository: index,
+        template_name: template_name.to_owned(),
+        template,
+        addons: Vec::new(),
+      });
+    }
 
     // Get workspace directory from user input
     let workspace_directory = if workspace_directory.is_none() {
@@ -129,59 +171,101 @@ impl Action {
       exit(1);
     }
 
-    let mut render_context = if template.meta.sub_type == TemplateType::PROJECT {
+    // The first template decides the kind of the workspace
+    let mut render_context = if selections[0].template.meta.sub_type == TemplateType::PROJECT {
       self.init_project(&ctx, &workspace_name, args)
     } else {
       self.init_snippet(&ctx, &workspace_name,args)
     };
 
-    if !ctx.yes {
-      // TODO think about
-      // Get template specific values
-      let values = match repository.get_template_values(&template_name) {
-        Ok(keys) => keys,
+    // Get the addons to layer after each template
+    for selection in selections.iter_mut() {
+      let addons = selection.template.meta.addons.clone().unwrap_or_default();
+      if addons.is_empty() {
+        continue;
+      }
+
+      let labels: Vec<String> = addons.iter().map(|addon| addon.get_label()).collect();
+      let defaults: Vec<bool> = addons.iter().map(|addon| addon.default.unwrap_or(false)).collect();
+
+      let text = format!("Please select addons of {}", selection.template_name);
+      let chosen = match input::multi_choice(&ctx, &text, &labels, &defaults) {
+        Ok(chosen) => chosen,
         Err(error) => {
           log::error!("{}", error);
-          println!("{}", error);
+          eprintln!("{}", error);
           exit(1);
         }
       };
 
-      for value in values {
-        let input = if value.default.is_some() {
-          // Get and parse default value
-          let default_value = renderer::render(&value.default.to_owned().unwrap(), &render_context);
-
-          match input::text_with_default(
-            &ctx,
-            &format!("Please enter {}", value.get_label()),
-            &default_value,
-          ) {
-            Ok(value) => value,
-            Err(error) => {
-              log::error!("{}", error);
-              String::from("")
-            }
-          }
-        } else {
-          let required = if value.required.is_some() {
-            value.required.to_owned().unwrap()
-          } else {
-            false
-          };
-
-          match input::text(&format!("Please enter {}", value.get_label()), !required) {
-            Ok(value) => value,
-            Err(error) => {
-              log::error!("{}", error);
-              String::from("")
-            }
-          }
-        };
+      selection.addons = addons
+        .into_iter()
+        .zip(labels.iter())
+        .filter(|(_, label)| chosen.contains(label))
+        .map(|(addon, _)| addon.template)
+        .collect();
+    }
+
+    // Collect the layers of the templates, layers shared with previous templates are only applied once
+    let mut plans: Vec<(CopyOptions, Vec<Template>)> = Vec::new();
+    let mut applied: Vec<PathBuf> = Vec::new();
+    for selection in selections.iter() {
+      let copy_options = CopyOptions {
+        template_name: selection.template_name.to_owned(),
+        addons: selection.addons.to_owned(),
+        skip: applied.to_owned(),
+        target: PathBuf::new(),
+        render_context: render_context.to_owned(),
+      };
+
+      let layers = match repository::get_layers(repositories[selection.repository].1.as_ref(), &copy_options) {
+        Ok(layers) => layers,
+        Err(error) => {
+          log::error!("{}", error);
+          eprintln!("{}", error);
+          exit(1);
+        }
+      };
+
+      applied.extend(layers.iter().map(|layer| layer.path.to_owned()));
+      plans.push((copy_options, layers));
+    }
 
-        // Update inputs map
-        render_context.values.insert(value.key, input);
+    // Get the values of all layers in the order they are applied, each value is only asked once.
+    // Defaults are rendered with the engine of the template that declares them.
+    let mut values: Vec<Value> = Vec::new();
+    let mut engines: HashMap<String, meta::Engine> = HashMap::new();
+    for layer in plans.iter().flat_map(|(_, layers)| layers.iter()) {
+      let layer_values = layer.meta.get_values();
+      for value in layer_values.iter().filter(|value| value.default.is_some()) {
+        engines.insert(value.key.to_owned(), layer.get_engine());
       }
+
+      meta::extend_values(&mut values, layer_values);
+    }
+
+    for value in values {
+      let engine = engines.get(&value.key).cloned().unwrap_or_else(meta::Engine::default);
+
+      // Fixed and hidden values are set without asking, with --yes all prompts take their default
+      let input = if !value.is_prompted() {
+        fixed_value(&value, &render_context, &engine)
+      } else if ctx.yes {
+        default_value(&value, &render_context, &engine)
+      } else {
+        prompt_value(&ctx, &value, &render_context, &engine)
+      };
+
+      let input = match input {
+        Ok(input) => input,
+        Err(error) => {
+          log::error!("{}", error);
+          serde_json::Value::Null
+        }
+      };
+
+      // Update inputs map
+      render_context.values.insert(value.key, input);
     }
 
     let tmp_dir = tempfile::Builder::new()
@@ -212,23 +296,40 @@ impl Action {
       }
     }
 
-    // Create copy options
-    let copy_options = CopyOptions {
-      template_name: template_name.to_owned(),
-      target: tmp_workspace_path.to_owned(),
-      render_context: render_context.to_owned(),
-    };
+    // The templates are applied to the temporary workspace with the final values
+    for (copy_options, _) in plans.iter_mut() {
+      copy_options.target = tmp_workspace_path.to_owned();
+      copy_options.render_context = render_context.to_owned();
+    }
 
-    // Copy the template
-    log::info!("Start processing template: {}", &template_name);
-    match repository.copy_template(&ctx, &copy_options) {
-      Ok(()) => (),
-      Err(error) => {
+    // Scripts of repositories that are not trusted need to be approved before anything runs
+    if !ctx.no_script {
+      if let Err(error) = self.approve_scripts(&mut ctx, &selections, &plans) {
         log::error!("{}", error);
         eprintln!("{}", error);
+        drop(tmp_dir);
         exit(1);
       }
-    };
+    }
+
+    // Apply the templates in order
+    for (selection, (copy_options, _)) in selections.iter().zip(plans.iter()) {
+      let repository = &repositories[selection.repository].1;
+
+      // Copy the template
+      log::info!("Start processing template: {}", &selection.template_name);
+      match repository.copy_template(&ctx, copy_options) {
+        Ok(()) => (),
+        Err(error) => {
+          log::error!("{}", error);
+          eprintln!("{}", error);
+
+          // Discard the temporary workspace, exit skips the cleanup on drop
+          drop(tmp_dir);
+          exit(1);
+        }
+      };
+    }
 
     // Create parent directories if they don´t exist
     let mut parent_dir = target_dir.to_owned();
@@ -259,9 +360,8 @@ impl Action {
       }
     }
 
-    let copy_options = dir::CopyOptions::new().content_only(true);
-    match dir::copy(tmp_workspace_path, target_dir, &copy_options) {
-      Ok(_result) => (),
+    match utils::copy_dir(tmp_workspace_path, &target_dir) {
+      Ok(()) => (),
       Err(error) => {
         log::error!("{}", error);
         eprintln!("{}", error);
@@ -272,21 +372,116 @@ impl Action {
     // Print success message
     out::success::workspace_created(&workspace_name);
 
-    // Get Template info
-    let template_info = match repository.get_template_info(&template_name) {
-      Ok(info) => info,
-      Err(error) => {
-        log::error!("{}", error);
-        eprintln!("{}", error);
-        exit(1);
+    // Print the info of every template, the info of the closest super template is used as fallback
+    for selection in selections.iter() {
+      let mut templates = match repositories[selection.repository].1.get_super_templates(&selection.template_name) {
+        Ok(templates) => templates,
+        Err(error) => {
+          log::error!("{}", error);
+          eprintln!("{}", error);
+          exit(1);
+        }
+      };
+      templates.push(selection.template.to_owned());
+
+      let info = templates
+        .iter()
+        .rev()
+        .find_map(|template| template.meta.info.as_ref().map(|info| (template, info)));
+      if let Some((template, info)) = info {
+        let info = renderer::render(info, &render_context, &template.get_engine());
+        out::success::workspace_info(&info);
       }
-    };
+    }
+  }
+
+  /// Show the rendered scripts of untrusted repositories and ask for approval, approvals are saved in the config.
+  /// Scripts of repositories with the policy never are skipped.
+  fn approve_scripts(
+    &self,
+    ctx: &mut context::Context,
+    selections: &[Selection],
+    plans: &[(CopyOptions, Vec<Template>)],
+  ) -> Result<(), RunError> {
+    // Untrusted scripts grouped by repository, or by template for templates outside of a configured repository
+    let mut untrusted: Vec<(Option<RepositoryOptions>, String, Vec<PendingScript>)> = Vec::new();
+
+    for (selection, (copy_options, layers)) in selections.iter().zip(plans.iter()) {
+      // Render the scripts like copy_template does
+      let mut renderer = renderer::Renderer::new(&copy_options.render_context)?;
+      if let Some(directory) = selection.template.path.parent() {
+        repository::register_partials(&mut renderer, directory, layers)?;
+      }
+
+      // The scripts of a layer can use values declared by all layers
+      let values: Vec<Value> = layers.iter().flat_map(|layer| layer.meta.get_values()).collect();
+
+      for layer in layers {
+        // Templates added with template add are pulled from their remote, their scripts are always reviewed
+        let options = layer.path.parent().and_then(|path| self.config.get_repository_config_by_directory(path));
+        let (policy, group) = match &options {
+          Some(options) => (options.get_script_policy(), format!("repository {}", options.name)),
+          None => (ScriptPolicy::Prompt, format!("template {}", layer.name)),
+        };
+
+        match policy {
+          ScriptPolicy::Always => continue,
+          ScriptPolicy::Never => {
+            log::info!("Skip scripts of {}: {} is not trusted", layer.name, group);
+            ctx.skip_scripts(&layer.path);
+            continue;
+          }
+          ScriptPolicy::Prompt => (),
+        }
+
+        let scripts: Vec<PendingScript> = layer
+          .get_scripts(ctx, &copy_options.target, &renderer, &values)?
+          .into_iter()
+          .filter(|(hash, _)| !options.as_ref().is_some_and(|options| options.is_script_trusted(hash)))
+          .inspect(|(hash, script)| log::info!("Script {} of {} is not trusted: {}", script.get_name(), layer.name, hash))
+          .map(|(hash, script)| (layer.name.to_owned(), hash, script))
+          .collect();
+        if scripts.is_empty() {
+          continue;
+        }
 
-    // Print template info
-    if template_info.is_some() {
-      let info = renderer::render(&template_info.unwrap(), &render_context);
-      out::success::workspace_info(&info);
+        match untrusted.iter_mut().find(|(_, name, _)| *name == group) {
+          Some((_, _, entries)) => entries.extend(scripts),
+          None => untrusted.push((options, group, scripts)),
+        }
+      }
+    }
+
+    if untrusted.is_empty() {
+      return Ok(());
+    }
+
+    let mut config = self.config.clone();
+    for (options, name, scripts) in untrusted.iter() {
+      out::info::untrusted_scripts(name, scripts);
+
+      // Approvals are never given implicitly
+      if ctx.yes {
+        return Err(RunError::Script(format!(
+          "Scripts of {} are not trusted, run without --yes to review them",
+          name
+        )));
+      }
+
+      if !input::confirm_with_default(ctx, "Do you trust these scripts?", false).unwrap_or(false) {
+        return Err(RunError::Script(format!("Scripts of {} were not approved", name)));
+      }
+
+      // Approvals of templates outside of a configured repository are not saved
+      if let Some(options) = options {
+        let hashes: Vec<String> = scripts.iter().map(|(_, hash, _)| hash.to_owned()).collect();
+        config.trust_scripts(&options.name, &hashes);
+      }
     }
+
+    config.save()?;
+
+    Ok(())
   }
 
   fn init_project(&self, ctx: &context::Context, workspace_name: &str, args: &ArgMatches) -> renderer::Context {
@@ -383,3 +578,121 @@ impl Action {
     return render_context;
   }
 }
+
+/// Script waiting for approval with the template it belongs to and the hash of its definition
+type PendingScript = (String, String, Script);
+
+/// Template of the workspace with the repository it is loaded from
+struct Selection {
+  /// Index of the loaded repository
+  repository: usize,
+  template_name: String,
+  template: Template,
+  /// Chosen addons of the template
+  addons: Vec<String>,
+}
+
+/// Ask for a template value with the prompt matching its type
+fn prompt_value(
+  ctx: &context::Context,
+  value: &Value,
+  render_context: &renderer::Context,
+  engine: &meta::Engine,
+) -> Result<serde_json::Value, RunError> {
+  // Get and parse default value
+  let default = value
+    .default
+    .as_ref()
+    .map(|default| renderer::render(default, render_context, engine));
+  let required = value.required.unwrap_or(false);
+
+  match value.get_kind() {
+    ValueType::String => {
+      let label = format!("Please enter {}", value.get_label());
+      let input = match default {
+        Some(default) => input::text_with_default(ctx, &label, &default)?,
+        None => input::text(&label, !required)?,
+      };
+
+      Ok(serde_json::Value::String(input))
+    }
+    ValueType::Bool => {
+      let default = default.map(|default| default.trim() == "true").unwrap_or(false);
+      let input = input::confirm_with_default(ctx, &value.get_label(), default)?;
+
+      Ok(serde_json::Value::Bool(input))
+    }
+    ValueType::Select => {
+      let choices = value.get_choices();
+      let index = default
+        .and_then(|default| choices.iter().position(|choice| *choice == default))
+        .unwrap_or(0);
+      let input = input::choice(ctx, &format!("Please select {}", value.get_label()), &choices, index)?;
+
+      Ok(serde_json::Value::String(input))
+    }
+    ValueType::Multiselect => {
+      let choices = value.get_choices();
+      let default = default.unwrap_or_default();
+      let defaults: Vec<&str> = default.split(',').map(|item| item.trim()).collect();
+      let checked: Vec<bool> = choices.iter().map(|choice| defaults.contains(&choice.as_str())).collect();
+      let input = input::multi_choice(ctx, &format!("Please select {}", value.get_label()), &choices, &checked)?;
+
+      Ok(serde_json::Value::from(input))
+    }
+    ValueType::Integer => {
+      let default = match default {
+        Some(default) => match default.trim().parse::<i64>() {
+          Ok(default) => Some(default),
+          Err(_) => return Err(RunError::Input(format!("Invalid default value for {}", value.key))),
+        },
+        None => None,
+      };
+      let input = input::number(ctx, &format!("Please enter {}", value.get_label()), default, !required)?;
+
+      Ok(input.map(serde_json::Value::from).unwrap_or(serde_json::Value::Null))
+    }
+  }
+}
+
+/// Value of a hidden template value, the fixed value or the default converted to the type
+fn fixed_value(value: &Value, render_context: &renderer::Context, engine: &meta::Engine) -> Result<serde_json::Value, RunError> {
+  match &value.value {
+    Some(fixed) => Ok(fixed.clone()),
+    None => default_value(value, render_context, engine),
+  }
+}
+
+/// Value a prompt is answered with when its default is accepted
+fn default_value(value: &Value, render_context: &renderer::Context, engine: &meta::Engine) -> Result<serde_json::Value, RunError> {
+  let default = value
+    .default
+    .as_ref()
+    .map(|default| renderer::render(default, render_context, engine));
+
+  match value.get_kind() {
+    ValueType::String => Ok(default.map(serde_json::Value::String).unwrap_or(serde_json::Value::Null)),
+    ValueType::Bool => Ok(serde_json::Value::Bool(default.is_some_and(|default| default.trim() == "true"))),
+    ValueType::Select => Ok(
+      default
+        .or_else(|| value.get_choices().first().cloned())
+        .map(serde_json::Value::String)
+        .unwrap_or(serde_json::Value::Null),
+    ),
+    ValueType::Multiselect => Ok(serde_json::Value::from(
+      default
+        .unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<&str>>(),
+    )),
+    ValueType::Integer => match default {
+      Some(default) => match default.trim().parse::<i64>() {
+        Ok(default) => Ok(serde_json::Value::from(default)),
+        Err(_) => Err(RunError::Input(format!("Invalid default value for {}", value.key))),
+      },
+      None => Ok(serde_json::Value::Null),
+    },
+  }
+}
@@ -270,6 +270,8 @@ impl Action {
       directory: None,
       description: Some(repository_description),
       git_options: Some(git_options),
+      script_policy: None,
+      trusted_scripts: Vec::new(),
     };
 
     let mut new_config = self.config.clone();
@@ -382,6 +384,8 @@ impl Action {
       directory: Some(absolute_directory.as_path().display().to_string()),
       description: Some(repository_description),
       git_options: None,
+      script_policy: None,
+      trusted_scripts: Vec::new(),
     };
 
     let mut new_config = self.config.clone();
@@ -107,6 +107,8 @@ impl Action {
       directory: None,
       description: Some(description),
       git_options: Some(git_options),
+      script_policy: None,
+      trusted_scripts: Vec::new(),
     };
 
     // Create repository
@@ -164,6 +166,8 @@ impl Action {
       directory: None,
       description: Some(description),
       git_options: None,
+      script_policy: None,
+      trusted_scripts: Vec::new(),
     };
 
     // Get directory from user input
@@ -1,8 +1,12 @@
+use std::fs;
+use std::path::Path;
 use std::process::exit;
 
 use crate::action::Action;
 use crate::cli::input;
+use crate::meta::{self, TemplateMeta};
 use crate::out;
+use crate::repository::extend;
 use crate::template;
 
 use clap::ArgMatches;
@@ -38,6 +42,68 @@ impl Action {
 
     out::info::display_template(&template);
 
+    // Resolve the extend chain against the templates next to the tested one
+    let repository_path = match directory_path.canonicalize() {
+      Ok(path) => path.parent().map(|parent| parent.to_owned()).unwrap_or(path),
+      Err(error) => {
+        log::error!("{}", error);
+        eprintln!("{}", error);
+        exit(1);
+      }
+    };
+    let repository_name = self.get_repository_name_by_directory(&repository_path);
+    let templates = load_templates(&repository_path);
+
+    match extend::Resolver::new(&self.config, &repository_name, &templates).resolve(&template) {
+      Ok(templates) => out::info::display_super_templates(&templates),
+      Err(error) => {
+        log::error!("{}", error);
+        eprintln!("{}", error);
+        exit(1);
+      }
+    };
+
     out::success::template_tested();
   }
 }
+
+impl Action {
+  /// Name of the configured repository in the directory, unknown directories are named after the folder
+  fn get_repository_name_by_directory(&self, directory: &Path) -> String {
+    match self.config.get_repository_config_by_directory(directory) {
+      Some(options) => options.name,
+      None => directory.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+    }
+  }
+}
+
+/// Load all templates of a directory, entries that are no templates are skipped
+fn load_templates(directory: &Path) -> Vec<template::Template> {
+  let entries = match fs::read_dir(directory) {
+    Ok(entries) => entries,
+    Err(error) => {
+      log::error!("{}", error);
+      return Vec::new();
+    }
+  };
+
+  let mut templates = Vec::new();
+  for entry in entries.flatten() {
+    let path = entry.path();
+    if !path.join("meta.json").exists() {
+      continue;
+    }
+
+    match meta::load::<TemplateMeta>(&path) {
+      Ok(meta) if meta.kind == meta::Type::TEMPLATE => (),
+      _ => continue,
+    };
+
+    match template::Template::new(&path) {
+      Ok(template) => templates.push(template),
+      Err(error) => log::error!("{}", error),
+    };
+  }
+
+  templates
+}
@@ -57,5 +57,15 @@ impl Action {
     };
 
     out::info::display_template(template);
+
+    // Resolve the extend chain to report circular or missing super templates
+    match repository.get_super_templates(&template_name) {
+      Ok(templates) => out::info::display_super_templates(&templates),
+      Err(error) => {
+        log::error!("{}", error);
+        eprintln!("{}", error);
+        exit(1);
+      }
+    };
   }
 }
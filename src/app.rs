@@ -17,13 +17,32 @@ pub fn build() -> Command {
         )
         .arg(arg!(--remote <URL> "Remote URL").required(false))
         .arg(arg!(-r --repository <NAME> "Repository to use").required(false))
-        .arg(arg!(-t --template <NAME> "Template to use for generation").required(false))
+        .arg(
+            arg!(-t --template <NAME> "Template to use for generation, repeat to apply several templates")
+                .action(ArgAction::Append)
+                .required(false),
+        )
         .arg(arg!(--username <USERNAME> "Username of the user").required(false))
         .arg(arg!(--email <EMAIL> "E-Mail of the user").required(false))
         .arg(
             arg!(no_script: --"no-script" "Don't execute template scripts")
                 .action(ArgAction::SetTrue)
                 .required(false),
+        )
+        .arg(
+            arg!(--strict "Abort if placeholders can't be resolved")
+                .action(ArgAction::SetTrue)
+                .required(false),
+        )
+        .arg(
+            arg!(script_timeout: --"script-timeout" <SECONDS> "Kill scripts running longer than the timeout")
+                .value_parser(clap::value_parser!(u64))
+                .required(false),
+        )
+        .arg(
+            arg!(-q --quiet "Only show the output of failed scripts")
+                .action(ArgAction::SetTrue)
+                .required(false),
         );
 
     let repository_subcommand = Command::new("repository")
@@ -3,7 +3,7 @@ use crate::error::RunError;
 use crate::utils;
 
 extern crate dialoguer;
-use dialoguer::{theme::ColorfulTheme, Input, Password, Select};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Password, Select};
 
 pub fn text(text: &str, allow_empty: bool) -> Result<String, RunError> {
   match Input::<String>::with_theme(&ColorfulTheme::default())
@@ -53,6 +53,112 @@ pub fn confirm(text: &str) -> bool {
   }
 }
 
+pub fn confirm_with_default(ctx: &Context, text: &str, default: bool) -> Result<bool, RunError> {
+  if ctx.yes {
+    return Ok(default);
+  }
+
+  match Confirm::with_theme(&ColorfulTheme::default())
+    .with_prompt(text)
+    .default(default)
+    .show_default(true)
+    .interact()
+  {
+    Ok(value) => Ok(value),
+    Err(error) => Err(RunError::IO(error)),
+  }
+}
+
+pub fn number(ctx: &Context, text: &str, default: Option<i64>, allow_empty: bool) -> Result<Option<i64>, RunError> {
+  if ctx.yes {
+    return Ok(default);
+  }
+
+  let theme = ColorfulTheme::default();
+  let mut input = Input::<String>::with_theme(&theme);
+  input
+    .with_prompt(text)
+    .allow_empty(allow_empty || default.is_some())
+    .validate_with(|value: &String| -> Result<(), String> {
+      if value.is_empty() || value.parse::<i64>().is_ok() {
+        Ok(())
+      } else {
+        Err(String::from("Please enter a number"))
+      }
+    });
+
+  if let Some(default) = default {
+    input.default(default.to_string()).show_default(true);
+  }
+
+  let value = match input.interact() {
+    Ok(value) => value,
+    Err(error) => return Err(RunError::IO(error)),
+  };
+
+  if value.is_empty() {
+    return Ok(default);
+  }
+
+  match value.parse::<i64>() {
+    Ok(value) => Ok(Some(value)),
+    Err(_) => Err(RunError::Input(String::from("Invalid number"))),
+  }
+}
+
+/// Select one of the given choices. Unlike `select` the choices are shown as they are.
+pub fn choice(ctx: &Context, text: &str, options: &[String], default: usize) -> Result<String, RunError> {
+  if options.is_empty() {
+    return Err(RunError::Input(String::from("No Options")));
+  };
+
+  if ctx.yes {
+    return Ok(options[default].to_owned());
+  }
+
+  let selection = match Select::with_theme(&ColorfulTheme::default())
+    .with_prompt(text)
+    .default(default)
+    .items(options)
+    .interact()
+  {
+    Ok(selection) => selection,
+    Err(error) => return Err(RunError::IO(error)),
+  };
+
+  Ok(options[selection].to_owned())
+}
+
+pub fn multi_choice(ctx: &Context, text: &str, options: &[String], defaults: &[bool]) -> Result<Vec<String>, RunError> {
+  if ctx.yes {
+    let mut result = Vec::new();
+    for (index, option) in options.iter().enumerate() {
+      if defaults.get(index) == Some(&true) {
+        result.push(option.to_owned());
+      }
+    }
+
+    return Ok(result);
+  }
+
+  let selections = match MultiSelect::with_theme(&ColorfulTheme::default())
+    .with_prompt(text)
+    .items(options)
+    .defaults(defaults)
+    .interact()
+  {
+    Ok(selections) => selections,
+    Err(error) => return Err(RunError::IO(error)),
+  };
+
+  let mut result = Vec::new();
+  for index in selections {
+    result.push(options[index].to_owned());
+  }
+
+  Ok(result)
+}
+
 pub fn password(text: &str) -> Result<String, RunError> {
   match Password::with_theme(&ColorfulTheme::default())
     .with_prompt(text)
@@ -35,6 +35,20 @@ pub struct RepositoryOptions {
   pub directory: Option<String>,
   pub description: Option<String>,
   pub git_options: Option<git::Options>,
+  /// Whether the scripts of the repository run without asking
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub script_policy: Option<ScriptPolicy>,
+  /// Content hashes of the approved scripts
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub trusted_scripts: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptPolicy {
+  Always,
+  Never,
+  Prompt,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -44,6 +58,22 @@ pub struct TemplateOptions {
   pub git_options: git::Options,
 }
 
+impl RepositoryOptions {
+  /// Scripts of external repositories are maintained locally and trusted by default
+  pub fn get_script_policy(&self) -> ScriptPolicy {
+    match &self.script_policy {
+      Some(policy) => policy.to_owned(),
+      None if self.kind == Some(String::from("external")) => ScriptPolicy::Always,
+      None => ScriptPolicy::Prompt,
+    }
+  }
+
+  /// Check if the script with the hash was approved before
+  pub fn is_script_trusted(&self, hash: &str) -> bool {
+    self.trusted_scripts.iter().any(|trusted| trusted == hash)
+  }
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct ConfigVersion {
   pub version: Option<String>,
@@ -82,6 +112,40 @@ impl Config {
     }
   }
 
+  /// Find the repository that is stored in the directory
+  pub fn get_repository_config_by_directory(&self, directory: &Path) -> Option<RepositoryOptions> {
+    let directory = directory.canonicalize().ok()?;
+
+    for options in &self.repositories {
+      let path = match &options.directory {
+        Some(path) if options.kind == Some(String::from("external")) => Path::new(path).to_owned(),
+        _ => self.repositories_dir.join(utils::lowercase(&options.name)),
+      };
+
+      if path.canonicalize().is_ok_and(|path| path == directory) {
+        return Some(options.clone());
+      }
+    }
+
+    None
+  }
+
+  /// Remember the approval of the scripts for the repository
+  pub fn trust_scripts(&mut self, name: &str, hashes: &[String]) {
+    let options = self
+      .repositories
+      .iter_mut()
+      .find(|options| utils::lowercase(&options.name) == utils::lowercase(name));
+
+    if let Some(options) = options {
+      for hash in hashes {
+        if !options.trusted_scripts.contains(hash) {
+          options.trusted_scripts.push(hash.to_owned());
+        }
+      }
+    }
+  }
+
   pub fn save(&self) -> Result<(), Error> {
     save_config(self)
   }
@@ -250,6 +314,8 @@ pub fn get_default_config() -> Config {
     directory: None,
     description: Some(String::from("Default template repository from tpoe")),
     git_options: Some(git_options),
+    script_policy: None,
+    trusted_scripts: Vec::new(),
   });
 
   let config = Config {
@@ -311,3 +377,79 @@ pub fn version() -> Result<Version, RunError> {
     Ok(version)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn repository(name: &str, kind: Option<&str>) -> RepositoryOptions {
+    RepositoryOptions {
+      name: String::from(name),
+      kind: kind.map(String::from),
+      directory: None,
+      description: None,
+      git_options: None,
+      script_policy: None,
+      trusted_scripts: Vec::new(),
+    }
+  }
+
+  fn config() -> Config {
+    Config {
+      version: String::from("2.0.0"),
+      repositories_dir: PathBuf::from("repositories"),
+      templates_dir: PathBuf::from("templates"),
+      repositories: vec![repository("Local", Some("external")), repository("remote", None)],
+      templates: Vec::new(),
+    }
+  }
+
+  #[test]
+  fn default_script_policies() {
+    let config = config();
+
+    assert_eq!(config.repositories[0].get_script_policy(), ScriptPolicy::Always);
+    assert_eq!(config.repositories[1].get_script_policy(), ScriptPolicy::Prompt);
+
+    let mut options = repository("local", Some("external"));
+    options.script_policy = Some(ScriptPolicy::Never);
+    assert_eq!(options.get_script_policy(), ScriptPolicy::Never);
+  }
+
+  #[test]
+  fn trust_scripts() {
+    let mut config = config();
+    let hashes = vec![String::from("a1"), String::from("b2")];
+
+    config.trust_scripts("remote", &hashes);
+    config.trust_scripts("REMOTE", &[String::from("b2"), String::from("c3")]);
+
+    let options = config.get_repository_config("remote").unwrap();
+    assert_eq!(options.trusted_scripts, vec!["a1", "b2", "c3"]);
+    assert!(options.is_script_trusted("a1"));
+    assert!(!options.is_script_trusted("d4"));
+
+    // Approvals only apply to their repository
+    assert!(!config.get_repository_config("local").unwrap().is_script_trusted("a1"));
+
+    // Unknown repositories are ignored
+    config.trust_scripts("missing", &hashes);
+    assert_eq!(config.repositories.len(), 2);
+  }
+
+  #[test]
+  fn serialize_trusted_scripts() {
+    let mut config = config();
+    let data = serde_yaml::to_string(&config).unwrap();
+    assert!(!data.contains("trusted_scripts"));
+    assert!(!data.contains("script_policy"));
+
+    config.trust_scripts("remote", &[String::from("a1")]);
+    config.repositories[1].script_policy = Some(ScriptPolicy::Prompt);
+    let data = serde_yaml::to_string(&config).unwrap();
+
+    let config: Config = serde_yaml::from_str(&data).unwrap();
+    assert_eq!(config.repositories[1].trusted_scripts, vec!["a1"]);
+    assert_eq!(config.repositories[1].script_policy, Some(ScriptPolicy::Prompt));
+  }
+}
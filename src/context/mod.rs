@@ -1,10 +1,18 @@
+use std::path::{Path, PathBuf};
+
 use clap::ArgMatches;
 
 pub struct Context {
   pub yes: bool,
   pub no_script: bool,
+  pub strict: bool,
   pub verbose: bool,
-
+  /// Show the output of scripts only on failure
+  pub quiet: bool,
+  /// Seconds after which scripts without their own timeout are killed
+  pub script_timeout: Option<u64>,
+  /// Templates whose scripts are not run
+  pub skip_scripts: Vec<PathBuf>,
 }
 
 impl Context {
@@ -12,7 +20,11 @@ impl Context {
     let mut ctx = Context {
       yes: false,
       no_script: false,
+      strict: false,
       verbose: false,
+      quiet: false,
+      script_timeout: None,
+      skip_scripts: Vec::new(),
     };
 
     ctx.set_yes(args.get_flag("yes"));
@@ -29,7 +41,27 @@ impl Context {
     self.no_script = ns;
   }
 
+  pub fn set_strict(&mut self, strict: bool) {
+    self.strict = strict;
+  }
+
   pub fn set_verbose(&mut self, verbose: bool) {
     self.verbose = verbose
   }
+
+  pub fn set_quiet(&mut self, quiet: bool) {
+    self.quiet = quiet;
+  }
+
+  pub fn set_script_timeout(&mut self, timeout: Option<u64>) {
+    self.script_timeout = timeout;
+  }
+
+  pub fn skip_scripts(&mut self, template: &Path) {
+    self.skip_scripts.push(template.to_owned());
+  }
+
+  pub fn is_script_enabled(&self, template: &Path) -> bool {
+    !self.no_script && !self.skip_scripts.iter().any(|path| path == template)
+  }
 }
@@ -8,7 +8,9 @@ pub enum RunError {
   IO(std::io::Error),
   Input(String),
   Meta(String),
+  Render(String),
   Repository(String),
+  Script(String),
   Template(String),
   Update(String),
 }
@@ -21,7 +23,9 @@ impl Display for RunError {
       Self::IO(e) => write!(f, "{}", e),
       Self::Input(e) => write!(f, "{}", e),
       Self::Meta(e) => write!(f, "Unable to load meta: Error: {}", e),
+      Self::Render(e) => write!(f, "Unable to render template! Error: {}", e),
       Self::Repository(e) => write!(f, "Unable to load repository! Error: {}", e),
+      Self::Script(e) => write!(f, "Script failed! Error: {}", e),
       Self::Template(e) => write!(f, "Unable to load template! Error: {}", e),
       Self::Update(e) => write!(f, "Unable to update! Error: {}", e),
     }
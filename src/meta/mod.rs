@@ -1,6 +1,8 @@
+use std::collections::{BTreeMap, HashMap};
+
+use indexmap::IndexMap;
 use std::fmt;
 use std::fs::File;
-use std::hash::{Hash, Hasher};
 use std::io::{Error, Read};
 use std::path::Path;
 
@@ -55,7 +57,11 @@ pub struct TemplateMeta {
   pub scripts: Option<Scripts>,
   pub extend: Option<Vec<String>>,
   pub exclude: Option<Vec<String>>,
+  pub conditions: Option<HashMap<String, String>>,
+  /// Kept in declaration order, the later of two equally specific patterns wins
+  pub merge: Option<IndexMap<String, MergeStrategy>>,
   pub renderer: Option<Renderer>,
+  pub addons: Option<Vec<Addon>>,
   pub info: Option<String>,
 }
 
@@ -70,33 +76,85 @@ impl fmt::Display for Type {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Renderer {
+  pub engine: Option<Engine>,
   pub exclude: Option<Vec<String>>,
+  pub include: Option<Vec<String>>,
+  pub strict: Option<bool>,
+  /// Drop files that render to blank content and prune directories left empty
+  pub remove_empty: Option<bool>,
+  pub delimiters: Option<DelimitersWrapper>,
+  /// Kept in declaration order, the later of two equally specific patterns wins
+  pub repeat: Option<IndexMap<String, String>>,
   pub values: Option<ValuesWrapper>,
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+  Handlebars,
+  #[serde(alias = "minijinja")]
+  Jinja,
+}
+
+impl Engine {
+  pub fn default() -> Self { Engine::Handlebars }
+}
+
+/// How a file is combined with a file that already exists in the workspace
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeStrategy {
+  Replace,
+  Append,
+  Union,
+  /// Deep merge of json, yaml and toml files
+  Merge,
+}
+
+impl MergeStrategy {
+  pub fn default() -> Self { MergeStrategy::Replace }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+  String,
+  #[serde(alias = "boolean")]
+  Bool,
+  Select,
+  Multiselect,
+  #[serde(alias = "number")]
+  Integer,
+}
+
+impl ValueType {
+  pub fn default() -> Self { ValueType::String }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Value {
   pub key: String,
+  #[serde(rename(serialize = "type", deserialize = "type"))]
+  pub kind: Option<ValueType>,
   pub label: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_default")]
   pub default: Option<String>,
   pub required: Option<bool>,
+  pub choices: Option<Vec<String>>,
+  /// Hidden values are not prompted, they get the fixed value or the default
+  pub hidden: Option<bool>,
+  /// Fixed value that is used without prompting
+  pub value: Option<serde_json::Value>,
 }
 
-impl PartialEq for Value {
-  fn eq(&self, other: &Self) -> bool {
-    self.key == other.key
+impl Value {
+  pub fn get_kind(&self) -> ValueType {
+    match &self.kind {
+      Some(kind) => kind.clone(),
+      None => ValueType::default(),
+    }
   }
-}
-
-impl Eq for Value {}
 
-impl Hash for Value {
-  fn hash<H: Hasher>(&self, state: &mut H) {
-      self.key.hash(state);
-  }
-}
-
-impl Value {
   pub fn get_label(&self) -> String {
     if self.label.is_some() {
       return self.label.clone().unwrap()
@@ -104,6 +162,99 @@ impl Value {
       return self.key.clone()
     }
   }
+
+  pub fn get_choices(&self) -> Vec<String> {
+    match &self.choices {
+      Some(choices) => choices.clone(),
+      None => vec![],
+    }
+  }
+
+  /// Check if the user is asked for the value
+  pub fn is_prompted(&self) -> bool {
+    !self.hidden.unwrap_or(false) && self.value.is_none()
+  }
+
+  /// Override all fields that are declared by the value of a more derived template
+  pub fn merge(&mut self, other: Value) {
+    if other.kind.is_some() {
+      self.kind = other.kind;
+    }
+    if other.label.is_some() {
+      self.label = other.label;
+    }
+    if other.default.is_some() {
+      self.default = other.default;
+    }
+    if other.required.is_some() {
+      self.required = other.required;
+    }
+    if other.choices.is_some() {
+      self.choices = other.choices;
+    }
+    if other.hidden.is_some() {
+      self.hidden = other.hidden;
+    }
+    if other.value.is_some() {
+      self.value = other.value;
+    }
+  }
+}
+
+/// Add the values of a more derived template.
+/// Redeclared values are merged field by field and keep the prompt position of the first declaration.
+pub fn extend_values(values: &mut Vec<Value>, overrides: Vec<Value>) {
+  for value in overrides {
+    match values.iter_mut().find(|existing| existing.key == value.key) {
+      Some(existing) => existing.merge(value),
+      None => values.push(value),
+    }
+  }
+}
+
+/// Optional template of the same repository that is layered after the main template
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Addon {
+  pub template: String,
+  pub label: Option<String>,
+  /// Preselect the addon
+  pub default: Option<bool>,
+}
+
+impl Addon {
+  pub fn get_label(&self) -> String {
+    match &self.label {
+      Some(label) => label.clone(),
+      None => self.template.clone(),
+    }
+  }
+}
+
+/// Accept booleans, numbers and arrays as default value next to plain strings.
+/// Arrays are joined with a comma and used as preselection of multiselect values.
+fn deserialize_default<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+  D: de::Deserializer<'de>,
+{
+  let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+
+  let default = match value {
+    None | Some(serde_json::Value::Null) => None,
+    Some(serde_json::Value::String(text)) => Some(text),
+    Some(serde_json::Value::Array(items)) => {
+      let items: Vec<String> = items
+        .iter()
+        .map(|item| match item {
+          serde_json::Value::String(text) => text.to_owned(),
+          _ => item.to_string(),
+        })
+        .collect();
+      Some(items.join(","))
+    }
+    Some(other) => Some(other.to_string()),
+  };
+
+  Ok(default)
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -113,10 +264,206 @@ pub enum ValuesWrapper {
   StringArray(Vec<String>)
 }
 
+/// Start and end delimiter for the whole template or per glob pattern
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum DelimitersWrapper {
+  Template((String, String)),
+  /// Kept in declaration order, the later of two equally specific patterns wins
+  Patterns(IndexMap<String, (String, String)>),
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Scripts {
-  pub before_install: Option<String>,
-  pub after_install: Option<String>,
+  pub before_install: Option<ScriptWrapper>,
+  pub after_install: Option<ScriptWrapper>,
+}
+
+/// Script given as plain command, with options or as ordered list of steps
+#[derive(Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum ScriptWrapper {
+  Command(String),
+  Script(Script),
+  Steps(Vec<Step>),
+}
+
+/// Single step of a script list, steps can't be nested
+#[derive(Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum Step {
+  Command(String),
+  Script(Script),
+}
+
+impl<'de> Deserialize<'de> for ScriptWrapper {
+  fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_any(ScriptVisitor { nested: false })
+  }
+}
+
+impl<'de> Deserialize<'de> for Step {
+  fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    match deserializer.deserialize_any(ScriptVisitor { nested: true })? {
+      ScriptWrapper::Command(command) => Ok(Step::Command(command)),
+      ScriptWrapper::Script(script) => Ok(Step::Script(script)),
+      ScriptWrapper::Steps(_) => Err(de::Error::custom("steps can't be nested")),
+    }
+  }
+}
+
+/// Deserialize scripts by their json type, errors of a step name the step and the field
+struct ScriptVisitor {
+  nested: bool,
+}
+
+impl<'de> de::Visitor<'de> for ScriptVisitor {
+  type Value = ScriptWrapper;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.nested {
+      f.write_str("a command or a script")
+    } else {
+      f.write_str("a command, a script or a list of steps")
+    }
+  }
+
+  fn visit_str<E: de::Error>(self, command: &str) -> Result<ScriptWrapper, E> {
+    Ok(ScriptWrapper::Command(command.to_owned()))
+  }
+
+  fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<ScriptWrapper, A::Error> {
+    let script = Script::deserialize(de::value::MapAccessDeserializer::new(map))?;
+
+    Ok(ScriptWrapper::Script(script))
+  }
+
+  fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<ScriptWrapper, A::Error> {
+    if self.nested {
+      return Err(de::Error::custom("steps can't be nested"));
+    }
+
+    let mut steps = Vec::new();
+    loop {
+      match seq.next_element::<Step>() {
+        Ok(Some(step)) => steps.push(step),
+        Ok(None) => break,
+        Err(error) => return Err(de::Error::custom(format!("invalid step {}: {}", steps.len() + 1, error))),
+      }
+    }
+
+    Ok(ScriptWrapper::Steps(steps))
+  }
+}
+
+/// Unknown fields are rejected, a misspelled step would be skipped otherwise
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Script {
+  /// Name shown in the progress of the init
+  pub name: Option<String>,
+  /// Command executed by the shell
+  pub run: Option<String>,
+  /// Program and arguments executed without a shell
+  pub args: Option<Vec<String>>,
+  /// Working directory relative to the workspace
+  pub cwd: Option<String>,
+  /// Sorted to keep the approval hash of the script stable
+  pub env: Option<BTreeMap<String, String>>,
+  /// Operating systems to run the script on, all if not set
+  pub os: Option<Vec<Os>>,
+  /// Expression on the values that needs to be truthy to run the script
+  pub when: Option<String>,
+  /// Seconds after which the script is killed
+  pub timeout: Option<u64>,
+  /// Keep initializing the workspace if the script fails
+  pub continue_on_error: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Os {
+  Linux,
+  Macos,
+  Windows,
+}
+
+impl Os {
+  pub fn is_current(&self) -> bool {
+    match self {
+      Os::Linux => cfg!(target_os = "linux"),
+      Os::Macos => cfg!(target_os = "macos"),
+      Os::Windows => cfg!(target_os = "windows"),
+    }
+  }
+}
+
+impl ScriptWrapper {
+  /// Flatten the script into its steps
+  pub fn to_scripts(&self) -> Vec<Script> {
+    match self {
+      ScriptWrapper::Command(command) => vec![Script {
+        run: Some(command.to_owned()),
+        ..Script::default()
+      }],
+      ScriptWrapper::Script(script) => vec![script.to_owned()],
+      ScriptWrapper::Steps(steps) => steps.iter().map(|step| step.to_script()).collect(),
+    }
+  }
+}
+
+impl Step {
+  pub fn to_script(&self) -> Script {
+    match self {
+      Step::Command(command) => Script {
+        run: Some(command.to_owned()),
+        ..Script::default()
+      },
+      Step::Script(script) => script.to_owned(),
+    }
+  }
+}
+
+impl Script {
+  pub fn get_name(&self) -> String {
+    match (&self.name, &self.run, &self.args) {
+      (Some(name), _, _) => name.to_owned(),
+      (None, Some(run), _) => run.to_owned(),
+      (None, None, Some(args)) => args.join(" "),
+      (None, None, None) => String::new(),
+    }
+  }
+
+  /// Check if the script has a command or a program to run
+  pub fn has_command(&self) -> bool {
+    self.run.as_ref().is_some_and(|run| !run.is_empty()) || self.args.as_ref().is_some_and(|args| !args.is_empty())
+  }
+
+  /// Check if the script runs on the current operating system
+  pub fn is_supported(&self) -> bool {
+    match &self.os {
+      Some(os) => os.iter().any(|os| os.is_current()),
+      None => true,
+    }
+  }
+
+  /// Replace the placeholders of the command, the working directory and the environment
+  pub fn render<F: Fn(&str) -> String>(&self, render: F) -> Script {
+    Script {
+      name: self.name.to_owned(),
+      run: self.run.as_ref().map(|run| render(run)),
+      args: self.args.as_ref().map(|args| args.iter().map(|arg| render(arg)).collect()),
+      cwd: self.cwd.as_ref().map(|cwd| render(cwd)),
+      env: self
+        .env
+        .as_ref()
+        .map(|env| env.iter().map(|(key, value)| (key.to_owned(), render(value))).collect()),
+      os: self.os.to_owned(),
+      when: self.when.to_owned(),
+      timeout: self.timeout,
+      continue_on_error: self.continue_on_error,
+    }
+  }
 }
 
 pub fn load<T: de::DeserializeOwned>(dir: &Path) -> Result<T, Error> {
@@ -174,10 +521,19 @@ impl TemplateMeta {
       }),
       extend: None,
       exclude: None,
+      conditions: None,
+      merge: None,
       renderer: Some(Renderer {
+        engine: None,
         exclude: None,
+        include: None,
+        strict: None,
+        remove_empty: None,
+        delimiters: None,
+        repeat: None,
         values: None,
       }),
+      addons: None,
       info: None,
     }
   }
@@ -200,9 +556,13 @@ impl TemplateMeta {
         for value in v {
           values.push(Value{
             key: value,
+            kind: None,
             label: None,
             default: None,
             required: None,
+            choices: None,
+            hidden: None,
+            value: None,
           })
         }
         return values
@@ -212,30 +572,18 @@ impl TemplateMeta {
     return values
   }
 
-  pub fn get_before_install_script(&self) -> Option<String> {
-    if self.scripts.is_some() {
-      let scripts = self.scripts.as_ref().unwrap();
-      if scripts.before_install.is_some() {
-        let script = scripts.before_install.to_owned().unwrap();
-
-        return Some(script);
-      }
+  pub fn get_before_install_scripts(&self) -> Vec<Script> {
+    match self.scripts.as_ref().and_then(|scripts| scripts.before_install.as_ref()) {
+      Some(script) => script.to_scripts(),
+      None => Vec::new(),
     }
-
-    return None;
   }
 
-  pub fn get_after_install_script(&self) -> Option<String> {
-    if self.scripts.is_some() {
-      let scripts = self.scripts.as_ref().unwrap();
-      if scripts.after_install.is_some() {
-        let script = scripts.after_install.to_owned().unwrap();
-
-        return Some(script);
-      }
+  pub fn get_after_install_scripts(&self) -> Vec<Script> {
+    match self.scripts.as_ref().and_then(|scripts| scripts.after_install.as_ref()) {
+      Some(script) => script.to_scripts(),
+      None => Vec::new(),
     }
-
-    return None;
   }
 }
 
@@ -252,15 +600,23 @@ mod tests {
     let result = vec![
       Value {
         key: String::from("value1"),
+        kind: None,
         label: None,
         default: None,
         required: None,
+        choices: None,
+        hidden: None,
+        value: None,
       },
       Value {
         key: String::from("value2"),
+        kind: None,
         label: None,
         default: None,
         required: None,
+        choices: None,
+        hidden: None,
+        value: None,
       },
     ];
 
@@ -287,15 +643,23 @@ mod tests {
     let result = vec![
       Value {
         key: String::from("value1"),
+        kind: None,
         label: None,
         default: None,
         required: None,
+        choices: None,
+        hidden: None,
+        value: None,
       },
       Value {
         key: String::from("value2"),
+        kind: None,
         label: None,
         default: None,
         required: None,
+        choices: None,
+        hidden: None,
+        value: None,
       },
     ];
 
@@ -307,4 +671,152 @@ mod tests {
 
     assert_eq!(values.len(), result.len());
   }
+
+  #[test]
+  fn deserialize_template_values_typed() {
+    let data = r#"[
+      {
+        "key": "use_docker",
+        "type": "bool",
+        "default": true
+      },
+      {
+        "key": "ci",
+        "type": "select",
+        "choices": ["github", "gitlab"]
+      },
+      {
+        "key": "features",
+        "type": "multiselect",
+        "choices": ["auth", "metrics", "tracing"],
+        "default": ["auth", "tracing"]
+      },
+      {
+        "key": "port",
+        "type": "integer",
+        "default": 8080
+      },
+      {
+        "key": "description"
+      }
+    ]"#;
+
+    let generic_values: ValuesWrapper = serde_json::from_str(&data).unwrap();
+    let values = match generic_values {
+      ValuesWrapper::Values(values) => values,
+      ValuesWrapper::StringArray(_) => panic!("wrong deserialization type"),
+    };
+
+    assert_eq!(values[0].get_kind(), ValueType::Bool);
+    assert_eq!(values[0].default, Some(String::from("true")));
+    assert_eq!(values[1].get_kind(), ValueType::Select);
+    assert_eq!(values[1].get_choices(), vec!["github", "gitlab"]);
+    assert_eq!(values[2].get_kind(), ValueType::Multiselect);
+    assert_eq!(values[2].default, Some(String::from("auth,tracing")));
+    assert_eq!(values[3].get_kind(), ValueType::Integer);
+    assert_eq!(values[3].default, Some(String::from("8080")));
+    assert_eq!(values[4].get_kind(), ValueType::String);
+  }
+
+  #[test]
+  fn extend_template_values() {
+    let data = r#"[
+      { "key": "name", "label": "Name" },
+      { "key": "port", "type": "integer", "default": 8080, "required": true },
+      { "key": "docker", "type": "bool" }
+    ]"#;
+    let mut values: Vec<Value> = serde_json::from_str(data).unwrap();
+
+    let data = r#"[
+      { "key": "description" },
+      { "key": "port", "label": "Port of the service", "default": 3000 },
+      { "key": "docker", "hidden": true, "value": false }
+    ]"#;
+    extend_values(&mut values, serde_json::from_str(data).unwrap());
+
+    let keys: Vec<&str> = values.iter().map(|value| value.key.as_str()).collect();
+    assert_eq!(keys, vec!["name", "port", "docker", "description"]);
+
+    assert_eq!(values[1].get_kind(), ValueType::Integer);
+    assert_eq!(values[1].get_label(), "Port of the service");
+    assert_eq!(values[1].default, Some(String::from("3000")));
+    assert_eq!(values[1].required, Some(true));
+    assert!(values[1].is_prompted());
+
+    assert_eq!(values[2].get_kind(), ValueType::Bool);
+    assert_eq!(values[2].value, Some(serde_json::Value::Bool(false)));
+    assert!(!values[2].is_prompted());
+  }
+
+  #[test]
+  fn deserialize_renderer_engine() {
+    let renderer: Renderer = serde_json::from_str(r#"{ "engine": "jinja" }"#).unwrap();
+    assert_eq!(renderer.engine, Some(Engine::Jinja));
+
+    let renderer: Renderer = serde_json::from_str(r#"{ "engine": "handlebars" }"#).unwrap();
+    assert_eq!(renderer.engine, Some(Engine::Handlebars));
+
+    let renderer: Renderer = serde_json::from_str("{}").unwrap();
+    assert_eq!(renderer.engine, None);
+  }
+
+  #[test]
+  fn deserialize_renderer_delimiters() {
+    let data = r#"{ "delimiters": ["[[", "]]"] }"#;
+    let renderer: Renderer = serde_json::from_str(&data).unwrap();
+    match renderer.delimiters {
+      Some(DelimitersWrapper::Template((start, end))) => {
+        assert_eq!(start, "[[");
+        assert_eq!(end, "]]");
+      }
+      _ => panic!("wrong deserialization type"),
+    };
+
+    let data = r#"{ "delimiters": { ".github/**": ["<%", "%>"] } }"#;
+    let renderer: Renderer = serde_json::from_str(&data).unwrap();
+    match renderer.delimiters {
+      Some(DelimitersWrapper::Patterns(patterns)) => {
+        assert_eq!(patterns[".github/**"], (String::from("<%"), String::from("%>")));
+      }
+      _ => panic!("wrong deserialization type"),
+    };
+  }
+
+  #[test]
+  fn deserialize_script_steps() {
+    let data = r#"{
+      "before_install": "npm ci",
+      "after_install": [
+        "git add .",
+        { "name": "Build", "args": ["cargo", "build"], "cwd": "app", "env": { "CI": "1" }, "os": ["linux", "macos"] }
+      ]
+    }"#;
+    let scripts: Scripts = serde_json::from_str(&data).unwrap();
+
+    let before_install = scripts.before_install.unwrap().to_scripts();
+    assert_eq!(before_install.len(), 1);
+    assert_eq!(before_install[0].run.as_deref(), Some("npm ci"));
+
+    let after_install = scripts.after_install.unwrap().to_scripts();
+    assert_eq!(after_install.len(), 2);
+    assert_eq!(after_install[0].get_name(), "git add .");
+    assert_eq!(after_install[1].get_name(), "Build");
+    assert_eq!(after_install[1].args, Some(vec![String::from("cargo"), String::from("build")]));
+    assert_eq!(after_install[1].os, Some(vec![Os::Linux, Os::Macos]));
+    assert!(after_install.iter().all(|script| script.has_command()));
+
+    // Misspelled fields are not skipped silently, the error names the field and the step
+    let error = serde_json::from_str::<Scripts>(r#"{ "after_install": { "command": "npm ci" } }"#).unwrap_err();
+    assert!(error.to_string().starts_with("unknown field `command`"));
+    let error = serde_json::from_str::<Scripts>(r#"{ "after_install": ["npm ci", { "run": "npm ci", "timout": 5 }] }"#).unwrap_err();
+    assert!(error.to_string().starts_with("invalid step 2: unknown field `timout`"));
+
+    // Steps are a single list
+    let error = serde_json::from_str::<Scripts>(r#"{ "after_install": ["npm ci", ["npm test"]] }"#).unwrap_err();
+    assert!(error.to_string().starts_with("invalid step 2: steps can't be nested"));
+    assert!(serde_json::from_str::<Scripts>(r#"{ "after_install": 5 }"#).is_err());
+
+    let scripts: Scripts = serde_json::from_str(r#"{ "after_install": { "name": "Install" } }"#).unwrap();
+    assert!(!scripts.after_install.unwrap().to_scripts()[0].has_command());
+  }
 }
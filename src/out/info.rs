@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::meta::Script;
 use crate::repository::Repository;
 use crate::template::Template;
 use crate::utils;
@@ -10,6 +11,33 @@ pub fn initiate_workspace() {
   println!("{}", text);
 }
 
+pub fn script_step(index: usize, total: usize, name: &str) {
+  println!("{} {}", format!("[{}/{}]", index, total).green(), name);
+}
+
+/// Scripts waiting for approval with the template they belong to
+pub fn untrusted_scripts(source: &str, scripts: &[(String, String, Script)]) {
+  let text = format!("Scripts of {} are not trusted yet:", source).yellow();
+  println!("{}", text);
+
+  for (template, _, script) in scripts {
+    println!("  {}: {}", template, script.get_name());
+
+    if let Some(run) = &script.run {
+      println!("    run: {}", run);
+    }
+    if let Some(args) = &script.args {
+      println!("    args: {}", args.join(" "));
+    }
+    if let Some(cwd) = &script.cwd {
+      println!("    cwd: {}", cwd);
+    }
+    for (key, value) in script.env.iter().flatten() {
+      println!("    env: {}={}", key, value);
+    }
+  }
+}
+
 pub fn list_templates(templates: &Vec<String>) {
   for template in templates {
     println!("{}", &utils::capitalize(template));
@@ -39,6 +67,16 @@ pub fn display_template(template: &Template) {
   }
 }
 
+/// Display the linearized extend chain, super templates are initialized in this order
+pub fn display_super_templates(templates: &[Template]) {
+  if templates.is_empty() {
+    return;
+  }
+
+  let names: Vec<&str> = templates.iter().map(|template| template.name.as_str()).collect();
+  println!("extend chain: {}", names.join(" -> "));
+}
+
 pub fn display_repository(repository: Box<dyn Repository>) {
   println!("name: {}", repository.get_config().name);
   if repository.get_config().description.is_some() {
@@ -4,3 +4,8 @@ pub fn script_execution_failed(logs: &str) {
   let text = format!("Script execution failed: {}", logs).yellow();
   println!("{}", text);
 }
+
+pub fn symlink_skipped(path: &str, link: &str) {
+  let text = format!("Skip link {}: {} points outside of the template", path, link).yellow();
+  println!("{}", text);
+}
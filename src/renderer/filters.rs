@@ -0,0 +1,40 @@
+extern crate convert_case;
+use convert_case::{Case, Casing};
+
+// Case conversion filters of the jinja engine, equivalent to the handlebars helpers
+
+pub fn uppercase_filter(value: String) -> String {
+  value.to_uppercase()
+}
+
+pub fn lowercase_filter(value: String) -> String {
+  value.to_lowercase()
+}
+
+pub fn camelcase_filter(value: String) -> String {
+  value.to_case(Case::Camel)
+}
+
+pub fn pascalcase_filter(value: String) -> String {
+  value.to_case(Case::Pascal)
+}
+
+pub fn snakecase_filter(value: String) -> String {
+  value.to_case(Case::Snake)
+}
+
+pub fn kebabcase_filter(value: String) -> String {
+  value.to_case(Case::Kebab)
+}
+
+pub fn constantcase_filter(value: String) -> String {
+  value.to_case(Case::UpperSnake)
+}
+
+pub fn capitalcase_filter(value: String) -> String {
+  value.to_case(Case::Title)
+}
+
+pub fn package_path_filter(value: String) -> String {
+  value.replace('.', "/")
+}
@@ -1,8 +1,16 @@
+use std::env;
+use std::fmt::Write;
+
+extern crate chrono;
+use chrono::{Datelike, Local};
 extern crate convert_case;
 use convert_case::{Case, Casing};
+extern crate uuid;
+use uuid::Uuid;
 
 use handlebars::{
-  Context, Handlebars, Helper, JsonRender, Output, RenderContext, RenderError,
+  handlebars_helper, BlockContext, Context, Handlebars, Helper, JsonRender, JsonValue as Json, Output, RenderContext, RenderError,
+  Renderable,
 };
 
 pub fn uppercase_helper(
@@ -140,3 +148,181 @@ pub fn capitalcase_helper(
   out.write(rendered.as_ref())?;
   Ok(())
 }
+
+pub fn package_path_helper(
+  h: &Helper,
+  _: &Handlebars,
+  _: &Context,
+  _: &mut RenderContext,
+  out: &mut dyn Output,
+) -> Result<(), RenderError> {
+  // get parameter from helper or throw an error
+  let param = h
+      .param(0)
+      .ok_or(RenderError::new("Param 0 is required for format helper."))?;
+
+  let rendered = param.value().render().to_string().replace('.', "/");
+  out.write(rendered.as_ref())?;
+  Ok(())
+}
+
+handlebars_helper!(date_helper: |*args| {
+  let format = args.first().and_then(|format| format.as_str()).unwrap_or("%Y-%m-%d");
+  format_date(format)?
+});
+
+handlebars_helper!(year_helper: | | Local::now().year());
+
+handlebars_helper!(uuid_helper: | | Uuid::new_v4().to_string());
+
+// Value of an environment variable with an optional fallback as second parameter
+handlebars_helper!(env_helper: |name: str, *args| {
+  match env::var(name) {
+    Ok(value) => value,
+    Err(_) => args.get(1).map(|fallback| fallback.render()).unwrap_or_default(),
+  }
+});
+
+handlebars_helper!(replace_helper: |value: Json, from: str, to: str| value.render().replace(from, to));
+
+handlebars_helper!(trim_helper: |value: Json| value.render().trim().to_owned());
+
+// First parameter if it is truthy, otherwise the fallback
+handlebars_helper!(default_helper: |value: Json, fallback: Json| {
+  if is_truthy(value) {
+    value.clone()
+  } else {
+    fallback.clone()
+  }
+});
+
+// Replaces the builtin `and` to accept any number of parameters
+handlebars_helper!(and_helper: |*args| args.iter().all(|value| is_truthy(value)));
+
+// Replaces the builtin `or` to accept any number of parameters
+handlebars_helper!(or_helper: |*args| args.iter().any(|value| is_truthy(value)));
+
+// Singular or plural form of a word depending on the count, the plural can be passed as third parameter
+handlebars_helper!(pluralize_helper: |word: str, count: Json, *args| {
+  let count = match count {
+    Json::Number(number) => number.as_f64().unwrap_or(0.0),
+    Json::String(text) => text.trim().parse::<f64>().unwrap_or(0.0),
+    Json::Array(items) => items.len() as f64,
+    _ => 0.0,
+  };
+
+  if count == 1.0 {
+    word.to_owned()
+  } else {
+    match args.get(2).and_then(|plural| plural.as_str()) {
+      Some(plural) => plural.to_owned(),
+      None => pluralize(word),
+    }
+  }
+});
+
+handlebars_helper!(json_helper: |value: Json, {pretty: bool = false}| {
+  let result = if pretty {
+    serde_json::to_string_pretty(value)
+  } else {
+    serde_json::to_string(value)
+  };
+
+  match result {
+    Ok(result) => result,
+    Err(error) => return Err(RenderError::new(format!("Unable to serialize value: {}", error))),
+  }
+});
+
+// Indent every non empty line by the given number of spaces
+handlebars_helper!(indent_helper: |value: Json, width: u64| {
+  let prefix = " ".repeat(width as usize);
+  value
+    .render()
+    .split('\n')
+    .map(|line| if line.trim().is_empty() { line.to_owned() } else { format!("{}{}", prefix, line) })
+    .collect::<Vec<String>>()
+    .join("\n")
+});
+
+fn format_date(format: &str) -> Result<String, RenderError> {
+  let mut date = String::new();
+  match write!(date, "{}", Local::now().format(format)) {
+    Ok(()) => Ok(date),
+    Err(_) => Err(RenderError::new(format!("Invalid date format: {}", format))),
+  }
+}
+
+/// Same truthiness as the builtin `if` helper
+fn is_truthy(value: &Json) -> bool {
+  match value {
+    Json::Null => false,
+    Json::Bool(value) => *value,
+    Json::Number(value) => value.as_f64().map(|value| value != 0.0).unwrap_or(true),
+    Json::String(value) => !value.is_empty(),
+    Json::Array(value) => !value.is_empty(),
+    Json::Object(value) => !value.is_empty(),
+  }
+}
+
+/// Simple english plural rules
+fn pluralize(word: &str) -> String {
+  let lowercase = word.to_lowercase();
+
+  if lowercase.ends_with('y') && !lowercase.ends_with("ay") && !lowercase.ends_with("ey") && !lowercase.ends_with("oy") && !lowercase.ends_with("uy") {
+    return format!("{}ies", &word[..word.len() - 1]);
+  }
+
+  if lowercase.ends_with('s') || lowercase.ends_with('x') || lowercase.ends_with('z') || lowercase.ends_with("ch") || lowercase.ends_with("sh") {
+    return format!("{}es", word);
+  }
+
+  format!("{}s", word)
+}
+
+/// Render the block for one element of a list like the `each` helper,
+/// the element is available as `this` together with `@index`, `@first` and `@last`
+pub fn repeat_item_helper<'reg, 'rc>(
+  h: &Helper<'reg, 'rc>,
+  r: &'reg Handlebars<'reg>,
+  ctx: &'rc Context,
+  rc: &mut RenderContext<'reg, 'rc>,
+  out: &mut dyn Output,
+) -> Result<(), RenderError> {
+  let list = h
+      .param(0)
+      .ok_or(RenderError::new("Param 0 is required for repeat_item helper."))?;
+  let index = h
+      .param(1)
+      .and_then(|index| index.value().as_u64())
+      .ok_or(RenderError::new("Param 1 is required for repeat_item helper."))? as usize;
+
+  let items = match list.value() {
+    Json::Array(items) => items,
+    _ => return Err(RenderError::new(format!("{} is not a list", list.relative_path().map(|path| path.as_str()).unwrap_or("")))),
+  };
+  let item = items
+      .get(index)
+      .ok_or(RenderError::new(format!("Index {} out of range", index)))?;
+
+  let mut block = BlockContext::new();
+  match list.context_path() {
+    Some(path) => {
+      let mut path = path.clone();
+      path.push(index.to_string());
+      *block.base_path_mut() = path;
+    }
+    None => block.set_base_value(item.clone()),
+  };
+  block.set_local_var("index", Json::from(index));
+  block.set_local_var("first", Json::from(index == 0));
+  block.set_local_var("last", Json::from(index == items.len() - 1));
+
+  rc.push_block(block);
+  if let Some(template) = h.template() {
+    template.render(r, ctx, rc, out)?;
+  }
+  rc.pop_block();
+
+  Ok(())
+}
@@ -1,13 +1,22 @@
 use log;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
 
+use crate::error::RunError;
+use crate::meta::Engine;
+
+mod filters;
 mod helpers;
 
 extern crate handlebars;
-use handlebars::Handlebars;
-use handlebars::no_escape;
+use handlebars::{no_escape, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError, ScopedJson};
+extern crate minijinja;
+use minijinja::{AutoEscape, Environment, UndefinedBehavior};
 extern crate serde;
 use serde::Serialize;
+use serde_json::Value;
 
 #[derive(Clone, Serialize, Debug)]
 pub struct Context {
@@ -15,51 +24,617 @@ pub struct Context {
   pub repository: String,
   pub username: String,
   pub email: String,
-  pub values: HashMap<String, String>,
-}
-
-pub fn render(text: &str, content: &Context) -> String {
-  // create the handlebars registry
-  let mut handlebars = Handlebars::new();
-
-  // register helper methods
-  handlebars.register_helper("uppercase", Box::new(helpers::uppercase_helper));
-  handlebars.register_helper("lowercase", Box::new(helpers::lowercase_helper));
-  handlebars.register_helper("camelcase", Box::new(helpers::camelcase_helper));
-  handlebars.register_helper("pascalcase", Box::new(helpers::pascalcase_helper));
-  handlebars.register_helper("snakecase", Box::new(helpers::snakecase_helper));
-  handlebars.register_helper("kebabcase", Box::new(helpers::kebabcase_helper));
-  handlebars.register_helper("constantcase", Box::new(helpers::constantcase_helper));
-  handlebars.register_helper("capitalcase", Box::new(helpers::capitalcase_helper));
-
-  // create the render context with the provided variables
-  let context = match handlebars::Context::wraps(content) {
-    Ok(context) => context,
-    Err(error) => {
-      log::error!("Error creating render context: Error: {}", error);
+  pub values: HashMap<String, Value>,
+}
+
+/// Problem found while rendering in strict mode
+#[derive(Clone, Debug)]
+pub struct Issue {
+  pub line: Option<usize>,
+  pub message: String,
+}
+
+/// Markers that enclose the handlebars expressions
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delimiters {
+  pub start: String,
+  pub end: String,
+}
+
+impl Default for Delimiters {
+  fn default() -> Delimiters {
+    Delimiters {
+      start: String::from("{{"),
+      end: String::from("}}"),
+    }
+  }
+}
+
+/// Element of the list a repeated entry is rendered for
+#[derive(Clone, Debug)]
+pub struct Item {
+  pub expression: String,
+  pub index: usize,
+}
+
+/// Engine, delimiters and the optional repeat item used to render a text
+#[derive(Clone, Copy, Debug)]
+pub struct Options<'a> {
+  pub engine: &'a Engine,
+  pub delimiters: &'a Delimiters,
+  pub item: Option<&'a Item>,
+}
+
+// Curly brackets outside of the expressions are replaced with these while rendering with custom delimiters
+const OPEN_PLACEHOLDER: &str = "\u{e000}tmpo-open\u{e000}";
+const CLOSE_PLACEHOLDER: &str = "\u{e000}tmpo-close\u{e000}";
+
+thread_local! {
+  // Placeholders that couldn't be resolved during a strict render on this thread,
+  // None if no strict render is running
+  static MISSING: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Handlebars registry and render context that are reused for all texts of a generation
+pub struct Renderer {
+  handlebars: Handlebars<'static>,
+  context: handlebars::Context,
+  jinja: Environment<'static>,
+  jinja_strict: Environment<'static>,
+  jinja_context: minijinja::Value,
+  /// Sources of the registered partials by name
+  partials: BTreeMap<String, String>,
+}
+
+impl Renderer {
+  pub fn new(content: &Context) -> Result<Renderer, RunError> {
+    // create the handlebars registry
+    let mut handlebars = Handlebars::new();
+
+    // register helper methods, arguments of helpers that transform a value have to resolve in strict renders
+    handlebars.register_helper("uppercase", checked(helpers::uppercase_helper));
+    handlebars.register_helper("lowercase", checked(helpers::lowercase_helper));
+    handlebars.register_helper("camelcase", checked(helpers::camelcase_helper));
+    handlebars.register_helper("pascalcase", checked(helpers::pascalcase_helper));
+    handlebars.register_helper("snakecase", checked(helpers::snakecase_helper));
+    handlebars.register_helper("kebabcase", checked(helpers::kebabcase_helper));
+    handlebars.register_helper("constantcase", checked(helpers::constantcase_helper));
+    handlebars.register_helper("capitalcase", checked(helpers::capitalcase_helper));
+    handlebars.register_helper("package_path", checked(helpers::package_path_helper));
+    handlebars.register_helper("date", Box::new(helpers::date_helper));
+    handlebars.register_helper("year", Box::new(helpers::year_helper));
+    handlebars.register_helper("uuid", Box::new(helpers::uuid_helper));
+    handlebars.register_helper("env", Box::new(helpers::env_helper));
+    handlebars.register_helper("replace", checked(helpers::replace_helper));
+    handlebars.register_helper("trim", checked(helpers::trim_helper));
+    handlebars.register_helper("default", Box::new(helpers::default_helper));
+    handlebars.register_helper("and", Box::new(helpers::and_helper));
+    handlebars.register_helper("or", Box::new(helpers::or_helper));
+    handlebars.register_helper("pluralize", checked(helpers::pluralize_helper));
+    handlebars.register_helper("json", checked(helpers::json_helper));
+    handlebars.register_helper("indent", checked(helpers::indent_helper));
+    handlebars.register_helper("repeat_item", Box::new(helpers::repeat_item_helper));
+    handlebars.register_helper("helperMissing", Box::new(missing_helper));
+    handlebars.register_helper("blockHelperMissing", Box::new(missing_helper));
+
+    handlebars.register_escape_fn(no_escape);
+
+    // create the render context with the provided variables
+    let context = match handlebars::Context::wraps(content) {
+      Ok(context) => context,
+      Err(error) => return Err(RunError::Render(format!("Error creating render context: {}", error))),
+    };
+
+    Ok(Renderer {
+      handlebars,
+      context,
+      jinja: jinja_environment(false),
+      jinja_strict: jinja_environment(true),
+      jinja_context: minijinja::Value::from_serialize(content),
+      partials: BTreeMap::new(),
+    })
+  }
+
+  /// Data of the render context
+  pub fn get_context(&self) -> &Value {
+    self.context.data()
+  }
+
+  /// Sources of the registered partials, later registrations replace earlier ones
+  pub fn get_partials(&self) -> &BTreeMap<String, String> {
+    &self.partials
+  }
+
+  /// Register every file of the directory as partial, named by its relative path without extension.
+  /// Partials registered later replace partials with the same name.
+  pub fn register_partials(&mut self, dir: &Path) -> Result<(), RunError> {
+    if !dir.is_dir() {
+      return Ok(());
+    }
+
+    self.register_partials_in(dir, dir)
+  }
+
+  fn register_partials_in(&mut self, root: &Path, dir: &Path) -> Result<(), RunError> {
+    for entry in fs::read_dir(dir)? {
+      let path = entry?.path();
+
+      if path.is_dir() {
+        self.register_partials_in(root, &path)?;
+        continue;
+      }
+
+      let name = path
+        .strip_prefix(root)
+        .unwrap()
+        .with_extension("")
+        .to_string_lossy()
+        .replace('\\', "/");
+      let text = fs::read_to_string(&path)?;
+
+      log::info!("Register partial: {}", name);
+      self.partials.insert(name.clone(), text.clone());
+
+      // Partials are written for one engine, they only have to be valid for one of them
+      let handlebars_result = self.handlebars.register_partial(&name, text.replace(r"\", r"\\"));
+
+      // Partials are available for include, import and extends of the jinja engine
+      let jinja_result = self
+        .jinja
+        .add_template_owned(name.clone(), text.clone())
+        .and_then(|_| self.jinja_strict.add_template_owned(name.clone(), text.clone()));
+
+      match (handlebars_result, jinja_result) {
+        (Err(handlebars_error), Err(jinja_error)) => {
+          return Err(RunError::Render(format!("Invalid partial {}: {}\n{:#}", name, handlebars_error, jinja_error)));
+        }
+        (Err(error), Ok(())) => log::info!("Partial {} is not available for handlebars: {}", name, error),
+        (Ok(()), Err(error)) => log::info!("Partial {} is not available for jinja: {}", name, error),
+        (Ok(()), Ok(())) => (),
+      };
+    }
+
+    Ok(())
+  }
+
+  /// Render the text with the engine and the default delimiters
+  pub fn render(&self, text: &str, engine: &Engine) -> String {
+    let options = Options {
+      engine,
+      delimiters: &Delimiters::default(),
+      item: None,
+    };
+
+    self.render_with(text, &options)
+  }
+
+  /// Render the text with the engine of the options, only expressions enclosed by the delimiters are replaced
+  pub fn render_with(&self, text: &str, options: &Options) -> String {
+    // Nothing to replace
+    if !has_expression(text, options) {
       return text.to_owned();
     }
-  };
 
-  let escaped_text = text.replace(r"\", r"\\");
-  handlebars.register_escape_fn(no_escape);
+    let source = translate(text, options.delimiters);
+    let result = match options.engine {
+      Engine::Handlebars => self.render_handlebars(&wrap_item(&source, options.item)),
+      Engine::Jinja => self
+        .jinja_context(options.item)
+        .and_then(|context| self.jinja.render_str(&source, context))
+        .map_err(|error| format!("{:#}", error)),
+    };
+
+    match result {
+      Ok(result) => restore(&result),
+      Err(error) => {
+        log::error!("Error rendering template: Error: {}", error);
+        text.to_owned()
+      }
+    }
+  }
+
+  /// Render the text with the engine of the options and report every missing variable, unknown helper and parse error.
+  /// Only expressions enclosed by the delimiters are replaced.
+  pub fn render_strict_with(&self, text: &str, options: &Options) -> Result<String, Vec<Issue>> {
+    // Nothing to replace
+    if !has_expression(text, options) {
+      return Ok(text.to_owned());
+    }
+
+    let source = translate(text, options.delimiters);
+    let result = match options.engine {
+      Engine::Handlebars => self.render_handlebars_strict(&wrap_item(&source, options.item)),
+      // The jinja engine stops at the first undefined value
+      Engine::Jinja => match self
+        .jinja_context(options.item)
+        .and_then(|context| self.jinja_strict.render_str(&source, context))
+      {
+        Ok(result) => Ok(result),
+        Err(error) => Err(vec![Issue {
+          line: error.line(),
+          message: match error.detail() {
+            Some(detail) => format!("{}: {}", error.kind(), detail),
+            None => error.kind().to_string(),
+          },
+        }]),
+      },
+    };
+
+    result.map(|result| restore(&result))
+  }
+
+  fn render_handlebars(&self, text: &str) -> Result<String, String> {
+    let escaped_text = text.replace(r"\", r"\\");
+
+    match self.handlebars.render_template_with_context(&escaped_text, &self.context) {
+      Ok(result) => Ok(result.replace(r"\\", r"\")),
+      Err(error) => Err(error.to_string()),
+    }
+  }
+
+  fn render_handlebars_strict(&self, text: &str) -> Result<String, Vec<Issue>> {
+    let escaped_text = text.replace(r"\", r"\\");
+
+    // Check the syntax first to get the position of parse errors
+    match handlebars::Template::compile(&escaped_text) {
+      Ok(_) => (),
+      Err(error) => {
+        return Err(vec![Issue {
+          line: error.line_no,
+          message: error.reason.to_string(),
+        }]);
+      }
+    };
+
+    MISSING.with(|missing| missing.replace(Some(Vec::new())));
+    let result = self.handlebars.render_template_with_context(&escaped_text, &self.context);
+    let missing = MISSING.with(|missing| missing.replace(None)).unwrap_or_default();
+
+    let mut issues = Vec::new();
+    for message in missing {
+      issues.push(Issue {
+        line: find_line(text, &message),
+        message,
+      });
+    }
+
+    match result {
+      Ok(result) => {
+        if issues.is_empty() {
+          return Ok(result.replace(r"\\", r"\"));
+        }
+      }
+      Err(error) => issues.push(Issue {
+        line: error.line_no,
+        message: error.desc,
+      }),
+    };
+
+    Err(issues)
+  }
+
+  /// Evaluate an expression like `values.docker` or `eq values.ci "gitlab"` (handlebars)
+  /// and `values.ci == "gitlab"` (jinja) and return if the result is truthy
+  pub fn evaluate(&self, expression: &str, options: &Options) -> bool {
+    let expression = strip_braces(expression);
+
+    if *options.engine == Engine::Jinja {
+      let result = self.jinja_context(options.item).and_then(|context| {
+        self.jinja.compile_expression(expression).and_then(|expression| expression.eval(context))
+      });
+
+      return match result {
+        Ok(result) => result.is_true(),
+        Err(error) => {
+          log::error!("Error evaluating expression: Error: {:#}", error);
+          false
+        }
+      };
+    }
+
+    // Helper calls have to be wrapped into a subexpression
+    let expression = if expression.contains(char::is_whitespace) && !expression.starts_with('(') {
+      format!("({})", expression)
+    } else {
+      expression.to_owned()
+    };
+
+    let text = format!("{{{{#if {}}}}}true{{{{/if}}}}", expression);
+    let options = Options {
+      delimiters: &Delimiters::default(),
+      ..*options
+    };
+
+    self.render_with(&text, &options) == "true"
+  }
+
+  /// Evaluate an expression that results in a list, like `values.entities`.
+  /// Undefined values result in an empty list.
+  pub fn resolve_list(&self, expression: &str, engine: &Engine) -> Result<Vec<Value>, RunError> {
+    let expression = strip_braces(expression);
+
+    let value = match engine {
+      Engine::Handlebars => {
+        let text = format!("{{{{json {}}}}}", expression);
+        match self.render_handlebars(&text) {
+          Ok(result) => serde_json::from_str(&result).unwrap_or(Value::Null),
+          Err(error) => return Err(RunError::Render(format!("Unable to resolve {}: {}", expression, error))),
+        }
+      }
+      Engine::Jinja => {
+        let result = self
+          .jinja
+          .compile_expression(expression)
+          .and_then(|expression| expression.eval(&self.jinja_context));
+        match result {
+          Ok(result) => serde_json::to_value(&result).unwrap_or(Value::Null),
+          Err(error) => return Err(RunError::Render(format!("Unable to resolve {}: {:#}", expression, error))),
+        }
+      }
+    };
+
+    match value {
+      Value::Array(items) => Ok(items),
+      Value::Null => Ok(Vec::new()),
+      _ => Err(RunError::Render(format!("{} is not a list", expression))),
+    }
+  }
+
+  /// Context of the jinja engine, the item of a repeated entry is available as `this` and `index`
+  fn jinja_context(&self, item: Option<&Item>) -> Result<minijinja::Value, minijinja::Error> {
+    let item = match item {
+      Some(item) => item,
+      None => return Ok(self.jinja_context.clone()),
+    };
+
+    let items = self.jinja.compile_expression(&item.expression)?.eval(&self.jinja_context)?;
+    let this = items.get_item(&minijinja::Value::from(item.index))?;
+
+    Ok(minijinja::context! {
+      this => this,
+      index => item.index,
+      ..self.jinja_context.clone()
+    })
+  }
+}
+
+/// Create the jinja environment with the case conversion filters
+fn jinja_environment(strict: bool) -> Environment<'static> {
+  let mut jinja = Environment::new();
+
+  jinja.add_filter("uppercase", filters::uppercase_filter);
+  jinja.add_filter("lowercase", filters::lowercase_filter);
+  jinja.add_filter("camelcase", filters::camelcase_filter);
+  jinja.add_filter("pascalcase", filters::pascalcase_filter);
+  jinja.add_filter("snakecase", filters::snakecase_filter);
+  jinja.add_filter("kebabcase", filters::kebabcase_filter);
+  jinja.add_filter("constantcase", filters::constantcase_filter);
+  jinja.add_filter("capitalcase", filters::capitalcase_filter);
+  jinja.add_filter("package_path", filters::package_path_filter);
+
+  // Files are written as they are rendered
+  jinja.set_auto_escape_callback(|_| AutoEscape::None);
+  jinja.set_keep_trailing_newline(true);
+
+  if strict {
+    jinja.set_undefined_behavior(UndefinedBehavior::Strict);
+  }
+
+  jinja
+}
 
-  // render the template
-  let result = match handlebars.render_template_with_context(&escaped_text, &context) {
-    Ok(result) => result,
+/// Render a single text, use a `Renderer` to render multiple texts with the same context
+pub fn render(text: &str, content: &Context, engine: &Engine) -> String {
+  match Renderer::new(content) {
+    Ok(renderer) => renderer.render(text, engine),
     Err(error) => {
-      log::error!("Error rendering template: Error: {}", error);
+      log::error!("{}", error);
       text.to_owned()
     }
+  }
+}
+
+/// Evaluate a single expression with the default engine, see `Renderer::evaluate`
+#[allow(dead_code)]
+pub fn evaluate(expression: &str, content: &Context) -> bool {
+  let options = Options {
+    engine: &Engine::Handlebars,
+    delimiters: &Delimiters::default(),
+    item: None,
   };
 
-  return result.replace(r"\\", r"\");
+  match Renderer::new(content) {
+    Ok(renderer) => renderer.evaluate(expression, &options),
+    Err(error) => {
+      log::error!("{}", error);
+      false
+    }
+  }
+}
+
+/// Called by handlebars for unknown helpers and variables that can't be resolved
+fn missing_helper(
+  h: &Helper,
+  _: &Handlebars,
+  _: &handlebars::Context,
+  _: &mut RenderContext,
+  _: &mut dyn Output,
+) -> Result<(), RenderError> {
+  let is_variable = h.params().is_empty() && h.hash().is_empty() && !h.is_block();
+  let message = if is_variable {
+    format!("Variable \"{}\" not found", h.name())
+  } else {
+    format!("Helper \"{}\" not defined", h.name())
+  };
+
+  let collected = MISSING.with(|missing| match missing.borrow_mut().as_mut() {
+    Some(missing) => {
+      missing.push(message);
+      true
+    }
+    None => false,
+  });
+
+  // Behave like handlebars without the hook outside of strict renders
+  if collected || is_variable {
+    return Ok(());
+  }
+
+  Err(RenderError::new(format!("Helper not defined: {:?}", h.name())))
+}
+
+/// Helper that reports arguments which can't be resolved during strict renders
+struct CheckedHelper(Box<dyn HelperDef + Send + Sync>);
+
+fn checked(helper: impl HelperDef + Send + Sync + 'static) -> Box<CheckedHelper> {
+  Box::new(CheckedHelper(Box::new(helper)))
+}
+
+impl CheckedHelper {
+  fn report_missing(&self, h: &Helper) {
+    for param in h.params().iter().chain(h.hash().values()) {
+      if let (true, Some(path)) = (param.is_value_missing(), param.relative_path()) {
+        let message = format!("Variable \"{}\" not found", path);
+        MISSING.with(|missing| missing.borrow_mut().as_mut().map(|missing| missing.push(message)));
+      }
+    }
+  }
+}
+
+impl HelperDef for CheckedHelper {
+  fn call_inner<'reg: 'rc, 'rc>(
+    &self,
+    h: &Helper<'reg, 'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc handlebars::Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+  ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+    // Helpers without a value fall back to call, report the arguments only once
+    let result = self.0.call_inner(h, r, ctx, rc);
+    if result.is_ok() {
+      self.report_missing(h);
+    }
+
+    result
+  }
+
+  fn call<'reg: 'rc, 'rc>(
+    &self,
+    h: &Helper<'reg, 'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc handlebars::Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+  ) -> HelperResult {
+    self.report_missing(h);
+    self.0.call(h, r, ctx, rc, out)
+  }
+}
+
+/// Check if the text contains anything the engine has to replace
+fn has_expression(text: &str, options: &Options) -> bool {
+  let start = &options.delimiters.start;
+
+  // Statements and comments of jinja are only available with the default delimiters
+  match options.engine {
+    Engine::Jinja if *options.delimiters == Delimiters::default() => {
+      text.contains(start) || text.contains("{%") || text.contains("{#")
+    }
+    _ => text.contains(start),
+  }
+}
+
+/// Render the text like inside of an `each` block for the item of a repeated entry
+fn wrap_item(text: &str, item: Option<&Item>) -> String {
+  match item {
+    Some(item) => format!("{{{{#repeat_item {} {}}}}}{}{{{{/repeat_item}}}}", item.expression, item.index, text),
+    None => text.to_owned(),
+  }
+}
+
+/// Remove the curly brackets around an expression
+fn strip_braces(expression: &str) -> &str {
+  let expression = expression.trim();
+
+  if expression.starts_with("{{") && expression.ends_with("}}") {
+    return expression[2..expression.len() - 2].trim();
+  }
+
+  expression
+}
+
+/// Turn the custom delimiters into expressions of the engine. Curly brackets outside of the expressions
+/// are hidden, so neither native syntax of the engine nor brackets next to a delimiter are interpreted
+fn translate(text: &str, delimiters: &Delimiters) -> String {
+  if *delimiters == Delimiters::default() {
+    return text.to_owned();
+  }
+
+  let hide = |text: &str| text.replace('{', OPEN_PLACEHOLDER).replace('}', CLOSE_PLACEHOLDER);
+
+  let mut result = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find(&delimiters.start) {
+    let expression = &rest[start + delimiters.start.len()..];
+    let end = match expression.find(&delimiters.end) {
+      Some(end) => end,
+      None => break,
+    };
+
+    result.push_str(&hide(&rest[..start]));
+    result.push_str("{{");
+    result.push_str(&expression[..end]);
+    result.push_str("}}");
+    rest = &expression[end + delimiters.end.len()..];
+  }
+  result.push_str(&hide(rest));
+
+  result
+}
+
+/// Bring back the native curly brackets hidden by `translate`
+fn restore(text: &str) -> String {
+  if !text.contains(OPEN_PLACEHOLDER) && !text.contains(CLOSE_PLACEHOLDER) {
+    return text.to_owned();
+  }
+
+  text.replace(OPEN_PLACEHOLDER, "{").replace(CLOSE_PLACEHOLDER, "}")
+}
+
+/// Find the line of the first placeholder that contains the quoted name of the message
+fn find_line(text: &str, message: &str) -> Option<usize> {
+  let name = message.split('"').nth(1)?;
+
+  let mut offset = 0;
+  while let Some(index) = text[offset..].find(name) {
+    let position = offset + index;
+    let before = &text[..position];
+
+    // Only count occurrences inside of a placeholder
+    let opened = before.rfind("{{");
+    let closed = before.rfind("}}");
+    if opened.is_some() && (closed.is_none() || opened > closed) {
+      return Some(before.matches('\n').count() + 1);
+    }
+
+    offset = position + name.len();
+  }
+
+  None
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  fn options<'a>(engine: &'a Engine, delimiters: &'a Delimiters) -> Options<'a> {
+    Options {
+      engine,
+      delimiters,
+      item: None,
+    }
+  }
+
   #[test]
   fn test_render_default() -> Result<(), Box<dyn std::error::Error>> {
     let text = "this is {{name}} an small test to test the basic {{repository}} features of the placeholder logic";
@@ -71,7 +646,7 @@ mod tests {
       values: HashMap::new(),
     };
 
-    let result = render(text, &content);
+    let result = render(text, &content, &Engine::Handlebars);
 
     assert_eq!(result, "this is Tmpo an small test to test the basic https://github.com/perryrh0dan/tmpo features of the placeholder logic");
 
@@ -83,8 +658,8 @@ mod tests {
     let text =
       "lets add one custom value: {{ values.full_name }} or a second one {{ values.last_name }}";
     let mut values = HashMap::new();
-    values.insert(String::from("full_name"), String::from("Thomas Pöhlmann"));
-    values.insert(String::from("last_name"), String::from("Pöhlmann"));
+    values.insert(String::from("full_name"), Value::from("Thomas Pöhlmann"));
+    values.insert(String::from("last_name"), Value::from("Pöhlmann"));
     let content: Context = Context {
       name: String::from("Tmpo"),
       repository: String::from("https://github.com/perryrh0dan/tmpo"),
@@ -93,7 +668,7 @@ mod tests {
       values: values,
     };
 
-    let result = render(text, &content);
+    let result = render(text, &content, &Engine::Handlebars);
 
     assert_eq!(
       result,
@@ -103,12 +678,36 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn test_render_typed_values() -> Result<(), Box<dyn std::error::Error>> {
+    let text =
+      "{{#if values.use_docker}}docker{{/if}}{{#unless values.use_ci}} no ci{{/unless}}:{{#each values.features}} {{this}}{{/each}}:{{ values.port }}";
+    let mut values = HashMap::new();
+    values.insert(String::from("use_docker"), Value::from(true));
+    values.insert(String::from("use_ci"), Value::from(false));
+    values.insert(String::from("features"), Value::from(vec!["auth", "tracing"]));
+    values.insert(String::from("port"), Value::from(8080));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+
+    let result = render(text, &content, &Engine::Handlebars);
+
+    assert_eq!(result, "docker no ci: auth tracing:8080");
+
+    Ok(())
+  }
+
   #[test]
   fn test_render_transformation_values() -> Result<(), Box<dyn std::error::Error>> {
     let text =
       "{{ camelcase values.full_name }},{{ constantcase values.full_name }},{{ kebabcase values.full_name }},{{ lowercase values.full_name }},{{ pascalcase values.full_name }},{{ snakecase values.full_name }},{{ uppercase values.full_name }},{{ capitalcase values.full_name }}";
     let mut values = HashMap::new();
-    values.insert(String::from("full_name"), String::from("ThomasPöhlmann"));
+    values.insert(String::from("full_name"), Value::from("ThomasPöhlmann"));
     let content: Context = Context {
       name: String::from("Tmpo"),
       repository: String::from("https://github.com/perryrh0dan/tmpo"),
@@ -117,7 +716,7 @@ mod tests {
       values: values,
     };
 
-    let result = render(text, &content);
+    let result = render(text, &content, &Engine::Handlebars);
 
     assert_eq!(
       result,
@@ -127,12 +726,272 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn test_render_date_values() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "{{ year }},{{ date }},{{ date \"%d.%m.%Y\" }}";
+    let values = HashMap::new();
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+
+    let result = render(text, &content, &Engine::Handlebars);
+
+    let now = chrono::Local::now();
+    assert_eq!(
+      result,
+      format!("{},{},{}", now.format("%Y"), now.format("%Y-%m-%d"), now.format("%d.%m.%Y"))
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_uuid_values() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "{{ uuid }} {{ uuid }}";
+    let values = HashMap::new();
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+
+    let result = render(text, &content, &Engine::Handlebars);
+    let uuids: Vec<&str> = result.split(' ').collect();
+
+    assert_eq!(uuids.len(), 2);
+    assert_eq!(uuids[0].len(), 36);
+    assert_eq!(&uuids[0][14..15], "4");
+    assert_ne!(uuids[0], uuids[1]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_env_values() -> Result<(), Box<dyn std::error::Error>> {
+    // Tests run in parallel, the variable is only used by this test
+    let name = format!("TMPO_TEST_RENDER_ENV_{}", std::process::id());
+    std::env::set_var(&name, "from env");
+    let text = format!(
+      "{{{{ env \"{}\" }}}},{{{{ env \"TMPO_TEST_RENDER_UNSET\" }}}},{{{{ env \"TMPO_TEST_RENDER_UNSET\" \"fallback\" }}}}",
+      name
+    );
+    let values = HashMap::new();
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+
+    let result = render(&text, &content, &Engine::Handlebars);
+    std::env::remove_var(&name);
+
+    assert_eq!(result, "from env,,fallback");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_string_values() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "{{ replace values.full_name \" \" \"_\" }},{{ trim values.padded }},{{ default values.unknown \"none\" }},{{ default values.full_name \"none\" }},{{ uppercase (trim values.padded) }}";
+    let mut values = HashMap::new();
+    values.insert(String::from("full_name"), Value::from("Thomas Pöhlmann"));
+    values.insert(String::from("padded"), Value::from("  tmpo \n"));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+
+    let result = render(text, &content, &Engine::Handlebars);
+
+    assert_eq!(result, "Thomas_Pöhlmann,tmpo,none,Thomas Pöhlmann,TMPO");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_logic_values() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "{{#if (eq values.ci \"gitlab\")}}eq{{/if}},{{#if (ne values.ci \"github\")}}ne{{/if}},{{#if (and values.docker values.ci values.port)}}and{{/if}},{{#if (or values.unknown false values.docker)}}or{{/if}},{{#if (not values.unknown)}}not{{/if}},{{#if (and values.docker values.unknown)}}wrong{{/if}}";
+    let mut values = HashMap::new();
+    values.insert(String::from("docker"), Value::from(true));
+    values.insert(String::from("ci"), Value::from("gitlab"));
+    values.insert(String::from("port"), Value::from(8080));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+
+    let result = render(text, &content, &Engine::Handlebars);
+
+    assert_eq!(result, "eq,ne,and,or,not,");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_pluralize_values() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "{{ pluralize \"service\" values.one }},{{ pluralize \"service\" values.two }},{{ pluralize \"entry\" values.features }},{{ pluralize \"class\" 0 }},{{ pluralize \"child\" 3 \"children\" }}";
+    let mut values = HashMap::new();
+    values.insert(String::from("one"), Value::from(1));
+    values.insert(String::from("two"), Value::from(2));
+    values.insert(String::from("features"), Value::from(vec!["auth", "tracing"]));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+
+    let result = render(text, &content, &Engine::Handlebars);
+
+    assert_eq!(result, "service,services,entries,classes,children");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_json_values() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "{{ json values.features }}\n{{ json values.name }}\n{{ json values.features pretty=true }}";
+    let mut values = HashMap::new();
+    values.insert(String::from("features"), Value::from(vec!["auth", "tracing"]));
+    values.insert(String::from("name"), Value::from("tmpo"));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+
+    let result = render(text, &content, &Engine::Handlebars);
+
+    assert_eq!(result, "[\"auth\",\"tracing\"]\n\"tmpo\"\n[\n  \"auth\",\n  \"tracing\"\n]");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_indent_values() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "steps:\n{{ indent values.steps 2 }}";
+    let mut values = HashMap::new();
+    values.insert(String::from("steps"), Value::from("- build\n\n- test"));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+
+    let result = render(text, &content, &Engine::Handlebars);
+
+    assert_eq!(result, "steps:\n  - build\n\n  - test");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_delimiters() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "name: [[ name ]]\nrun: echo ${{ github.sha }} [[#if values.docker]]docker[[/if]] {{{ raw }}}";
+    let mut values = HashMap::new();
+    values.insert(String::from("docker"), Value::from(true));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+    let renderer = Renderer::new(&content).unwrap();
+    let delimiters = Delimiters {
+      start: String::from("[["),
+      end: String::from("]]"),
+    };
+
+    let result = renderer.render_with(text, &options(&Engine::Handlebars, &delimiters));
+    assert_eq!(result, "name: Tmpo\nrun: echo ${{ github.sha }} docker {{{ raw }}}");
+
+    let issues = renderer.render_strict_with("{{ native }}\n[[ values.unknown ]]", &options(&Engine::Handlebars, &delimiters)).unwrap_err();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].line, Some(2));
+
+    // Native delimiters are left untouched if no custom delimiter is found
+    let result = renderer.render_with("{{ name }}", &options(&Engine::Handlebars, &delimiters));
+    assert_eq!(result, "{{ name }}");
+
+    // Brackets next to the delimiters are text
+    let delimiters = Delimiters {
+      start: String::from("<%"),
+      end: String::from("%>"),
+    };
+    let result = renderer.render_with("{<% name %>} {{<% name %>}} {{{<% name %>}}}", &options(&Engine::Handlebars, &delimiters));
+    assert_eq!(result, "{Tmpo} {{Tmpo}} {{{Tmpo}}}");
+
+    // Native syntax of jinja is text, brackets inside of the expressions are kept
+    let text = "{% if true %}{# note #}{% endif %} <% {'a': name}['a'] %> {<% name | lower %>}";
+    let result = renderer.render_with(text, &options(&Engine::Jinja, &delimiters));
+    assert_eq!(result, "{% if true %}{# note #}{% endif %} Tmpo {tmpo}");
+
+    // A start without an end is text
+    let result = renderer.render_with("<% name %> {{ name }} <% name", &options(&Engine::Handlebars, &delimiters));
+    assert_eq!(result, "Tmpo {{ name }} <% name");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_jinja() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "{% macro item(name) %}- {{ name | kebabcase }}{% endmacro %}{{ name | uppercase }} {{ values.full_name | snakecase }}\n{% for feature in values.features %}{{ item(feature) }}\n{% endfor %}{% if values.port > 8000 %}port {{ values.port }}{% endif %}\n";
+    let mut values = HashMap::new();
+    values.insert(String::from("full_name"), Value::from("ThomasPöhlmann"));
+    values.insert(String::from("features"), Value::from(vec!["AuthService", "Tracing"]));
+    values.insert(String::from("port"), Value::from(8080));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+    let renderer = Renderer::new(&content).unwrap();
+
+    let result = renderer.render_with(text, &options(&Engine::Jinja, &Delimiters::default()));
+    assert_eq!(result, "TMPO thomas_pöhlmann\n- auth-service\n- tracing\nport 8080\n");
+
+    // Backslashes don't need any escaping
+    let result = renderer.render_with(r"C:\test\{{ name }}.ts", &options(&Engine::Jinja, &Delimiters::default()));
+    assert_eq!(result, r"C:\test\Tmpo.ts");
+
+    let issues = renderer.render_strict_with("{{ name }}\n{{ values.unknown }}", &options(&Engine::Jinja, &Delimiters::default())).unwrap_err();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].line, Some(2));
+
+    assert!(renderer.evaluate("values.port == 8080 and name == 'Tmpo'", &options(&Engine::Jinja, &Delimiters::default())));
+    assert!(!renderer.evaluate("values.unknown", &options(&Engine::Jinja, &Delimiters::default())));
+
+    Ok(())
+  }
+
   #[test]
   fn test_render_unknown() -> Result<(), Box<dyn std::error::Error>> {
     let text = "lets add one custom value: {{ values.full_name }} or a second one {{ values.last_name }} and an unknown: {{ values.first_name }}";
     let mut values = HashMap::new();
-    values.insert(String::from("full_name"), String::from("Thomas Pöhlmann"));
-    values.insert(String::from("last_name"), String::from("Pöhlmann"));
+    values.insert(String::from("full_name"), Value::from("Thomas Pöhlmann"));
+    values.insert(String::from("last_name"), Value::from("Pöhlmann"));
     let content: Context = Context {
       name: String::from("Tmpo"),
       repository: String::from("https://github.com/perryrh0dan/tmpo"),
@@ -141,7 +1000,7 @@ mod tests {
       values: values,
     };
 
-    let result = render(text, &content);
+    let result = render(text, &content, &Engine::Handlebars);
 
     assert_eq!(
       result,
@@ -151,12 +1010,35 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn test_render_package_path() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "src/main/java/{{ package_path values.package }}/App.java";
+    let mut values = HashMap::new();
+    values.insert(String::from("package"), Value::from("com.acme.app"));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+    let renderer = Renderer::new(&content).unwrap();
+
+    assert_eq!(renderer.render(text, &Engine::Handlebars), "src/main/java/com/acme/app/App.java");
+    assert_eq!(
+      renderer.render_with("{{ values.package | package_path }}", &options(&Engine::Jinja, &Delimiters::default())),
+      "com/acme/app"
+    );
+
+    Ok(())
+  }
+
   #[test]
   fn test_render_transformation_value_error() -> Result<(), Box<dyn std::error::Error>> {
     let text =
       "{{ testcase values.full_name }}";
     let mut values = HashMap::new();
-    values.insert(String::from("full_name"), String::from("ThomasPöhlmann"));
+    values.insert(String::from("full_name"), Value::from("ThomasPöhlmann"));
     let content: Context = Context {
       name: String::from("Tmpo"),
       repository: String::from("https://github.com/perryrh0dan/tmpo"),
@@ -165,7 +1047,7 @@ mod tests {
       values: values,
     };
 
-    let result = render(text, &content);
+    let result = render(text, &content, &Engine::Handlebars);
 
     assert_eq!(
       result,
@@ -179,7 +1061,7 @@ mod tests {
   fn test_render_path() -> Result<(), Box<dyn std::error::Error>> {
     let text = r"C:\test\test1234\{{name}}.graphql.ts";
     let mut values = HashMap::new();
-    values.insert(String::from("name"), String::from("ProductView"));
+    values.insert(String::from("name"), Value::from("ProductView"));
     let content: Context = Context {
       name: String::from("Tmpo"),
       repository: String::from("https://github.com/perryrh0dan/tmpo"),
@@ -188,7 +1070,7 @@ mod tests {
       values: values,
     };
 
-    let result = render(text, &content);
+    let result = render(text, &content, &Engine::Handlebars);
 
     assert_eq!(
       result,
@@ -202,7 +1084,7 @@ mod tests {
   fn test_render_escaped_string() -> Result<(), Box<dyn std::error::Error>> {
     let text = r#""prettier:cli": "lb-prettier \"**/*.ts\" \"**/*.js\"","#;
     let mut values = HashMap::new();
-    values.insert(String::from("name"), String::from("ProductView"));
+    values.insert(String::from("name"), Value::from("ProductView"));
     let content: Context = Context {
       name: String::from("Tmpo"),
       repository: String::from("https://github.com/perryrh0dan/tmpo"),
@@ -211,7 +1093,7 @@ mod tests {
       values: values,
     };
 
-    let result = render(text, &content);
+    let result = render(text, &content, &Engine::Handlebars);
 
     assert_eq!(
       result,
@@ -220,4 +1102,133 @@ mod tests {
 
     Ok(())
   }
+
+  #[test]
+  fn test_evaluate() -> Result<(), Box<dyn std::error::Error>> {
+    let mut values = HashMap::new();
+    values.insert(String::from("docker"), Value::from(true));
+    values.insert(String::from("ci"), Value::from("gitlab"));
+    values.insert(String::from("features"), Value::from(Vec::<String>::new()));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+
+    assert!(evaluate("values.docker", &content));
+    assert!(evaluate("{{ values.docker }}", &content));
+    assert!(evaluate(r#"eq values.ci "gitlab""#, &content));
+    assert!(!evaluate(r#"(eq values.ci "github")"#, &content));
+    assert!(!evaluate("values.features", &content));
+    assert!(!evaluate("values.unknown", &content));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_strict() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "name: {{ name }}\nfull name: {{ values.full_name }}\nfirst name: {{ values.first_name }}\n{{ testcase values.full_name }}";
+    let mut values = HashMap::new();
+    values.insert(String::from("full_name"), Value::from("Thomas Pöhlmann"));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+    let renderer = Renderer::new(&content).unwrap();
+
+    let issues = match renderer.render_strict_with(text, &options(&Engine::Handlebars, &Delimiters::default())) {
+      Ok(_) => panic!("missing placeholders not reported"),
+      Err(issues) => issues,
+    };
+
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].line, Some(3));
+    assert_eq!(issues[0].message, "Variable \"values.first_name\" not found");
+    assert_eq!(issues[1].line, Some(4));
+    assert_eq!(issues[1].message, "Helper \"testcase\" not defined");
+
+    // Arguments of helpers that transform a value have to resolve, conditions and defaults may be missing
+    let text = "{{uppercase values.full_name}}\n{{uppercase values.missing}}\n{{replace (lowercase values.other) \"a\" \"b\"}}\n{{default values.missing \"x\"}}{{#if values.missing}}{{/if}}";
+    let issues = renderer.render_strict_with(text, &options(&Engine::Handlebars, &Delimiters::default())).unwrap_err();
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].line, Some(2));
+    assert_eq!(issues[0].message, "Variable \"values.missing\" not found");
+    assert_eq!(issues[1].line, Some(3));
+    assert_eq!(issues[1].message, "Variable \"values.other\" not found");
+
+    let result = renderer.render_strict_with("name: {{ name }}", &options(&Engine::Handlebars, &Delimiters::default()));
+    assert_eq!(result.unwrap(), "name: Tmpo");
+
+    let issues = renderer.render_strict_with("line\n{{#if name}}", &options(&Engine::Handlebars, &Delimiters::default())).unwrap_err();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].line, Some(2));
+
+    // Texts without placeholders are returned unchanged
+    let result = renderer.render_strict_with("plain text with a \\ and {single} braces", &options(&Engine::Handlebars, &Delimiters::default()));
+    assert_eq!(result.unwrap(), "plain text with a \\ and {single} braces");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_partials() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::Builder::new().tempdir()?;
+    fs::create_dir_all(dir.path().join("ci"))?;
+    fs::write(dir.path().join("license_header.txt"), "// Copyright {{ username }}")?;
+    fs::write(dir.path().join("ci/stages.yml"), r"stages: [build]\n")?;
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values: HashMap::new(),
+    };
+
+    let mut renderer = Renderer::new(&content).unwrap();
+    renderer.register_partials(dir.path()).unwrap();
+
+    assert_eq!(renderer.render("{{> license_header}}\nfn main() {}", &Engine::Handlebars), "// Copyright perryrh0dan\nfn main() {}");
+    assert_eq!(renderer.render("{{> ci/stages}}", &Engine::Handlebars), r"stages: [build]\n");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_repeat_item() -> Result<(), Box<dyn std::error::Error>> {
+    let mut values = HashMap::new();
+    values.insert(String::from("entities"), serde_json::json!([{ "name": "user" }, { "name": "order" }]));
+    let content: Context = Context {
+      name: String::from("Tmpo"),
+      repository: String::from("https://github.com/perryrh0dan/tmpo"),
+      username: String::from("perryrh0dan"),
+      email: String::from("thomaspoehlmann96@googlemail.com"),
+      values,
+    };
+    let renderer = Renderer::new(&content).unwrap();
+
+    let items = renderer.resolve_list("values.entities", &Engine::Handlebars).unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(renderer.resolve_list("values.unknown", &Engine::Jinja).unwrap().len(), 0);
+    assert!(renderer.resolve_list("name", &Engine::Handlebars).is_err());
+
+    let item = Item { expression: String::from("values.entities"), index: 1 };
+    let delimiters = Delimiters::default();
+    let handlebars = Options { item: Some(&item), ..options(&Engine::Handlebars, &delimiters) };
+    assert_eq!(
+      renderer.render_with("{{@index}}: {{pascalcase name}} of {{@root.name}} {{#if @last}}last{{/if}}", &handlebars),
+      "1: Order of Tmpo last"
+    );
+    assert!(renderer.evaluate(r#"eq name "order""#, &handlebars));
+
+    let jinja = Options { item: Some(&item), ..options(&Engine::Jinja, &delimiters) };
+    assert_eq!(renderer.render_with("{{ index }}: {{ this.name }} of {{ name }}", &jinja), "1: order of Tmpo");
+    assert!(renderer.evaluate("this.name == 'order'", &jinja));
+
+    Ok(())
+  }
 }
@@ -1,4 +1,3 @@
-use linked_hash_set::LinkedHashSet;
 use std::fs;
 use log;
 use std::io::{Error};
@@ -8,8 +7,9 @@ use crate::config::{Config, RepositoryOptions, TemplateOptions};
 use crate::context::Context;
 use crate::error::RunError;
 use crate::git;
-use crate::meta::{self, TemplateMeta, Value};
-use crate::repository::{CopyOptions, Repository};
+use crate::meta::{self, TemplateMeta};
+use crate::renderer;
+use crate::repository::{self, CopyOptions, Repository};
 use crate::template;
 use crate::utils;
 
@@ -27,14 +27,25 @@ impl Repository for DefaultRepository {
       directory: None,
       description: Some(String::from("Mono repository templates")),
       git_options: None,
+      script_policy: None,
+      trusted_scripts: Vec::new(),
     };
   }
 
   fn copy_template(&self, ctx: &Context, opts: &CopyOptions) -> Result<(), RunError> {
     let template = self.get_template_by_name(&opts.template_name)?;
 
-    // Initialize template
-    template.init(ctx, &opts.target, &opts.render_context)?;
+    let layers = repository::get_layers(self, opts)?;
+
+    let mut renderer = renderer::Renderer::new(&opts.render_context)?;
+    for layer in layers.iter() {
+      renderer.register_partials(&layer.path.join(template::PARTIALS_DIR))?;
+    }
+
+    // Initialize template and addons
+    for layer in layers.iter() {
+      layer.init(ctx, &opts.target, &renderer)?;
+    }
 
     // Create info file
     template.create_info(&opts.target)?;
@@ -42,21 +53,6 @@ impl Repository for DefaultRepository {
     Ok(())
   }
 
-  fn get_template_info(&self, template_name: &str) -> Result<Option<String>, RunError> {
-    let template = self.get_template_by_name(template_name)?;
-
-    Ok(template.meta.info.to_owned())
-  }
-
-  fn get_template_values(&self, template_name: &str) -> Result<LinkedHashSet<Value>, RunError> {
-    let template = self.get_template_by_name(template_name)?;
-
-    let mut values = LinkedHashSet::new();
-    values.extend(template.meta.get_values());
-
-    Ok(values)
-  }
-
   /// Return list of all template names in this repository
   fn get_template_names(&self) -> Vec<String> {
     let mut templates = Vec::<String>::new();
@@ -78,6 +74,11 @@ impl Repository for DefaultRepository {
 
     return Err(RunError::Template(String::from("Not found")));
   }
+
+  /// Templates of the default repository can't extend other templates
+  fn get_super_templates(&self, _template_name: &str) -> Result<Vec<template::Template>, RunError> {
+    Ok(Vec::new())
+  }
 }
 
 impl DefaultRepository {
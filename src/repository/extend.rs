@@ -0,0 +1,130 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::config::Config;
+use crate::error::RunError;
+use crate::repository::{self, Repository};
+use crate::template::Template;
+use crate::utils;
+
+/// Resolves the extend references of a template into a linear list of super templates
+pub struct Resolver<'a> {
+  config: &'a Config,
+  repository: String,
+  templates: &'a [Template],
+  others: HashMap<String, Result<Box<dyn Repository>, String>>,
+}
+
+/// Progress of a single resolution
+#[derive(Default)]
+struct State {
+  templates: Vec<Template>,
+  resolved: HashSet<String>,
+  path: Vec<String>,
+  errors: Vec<String>,
+}
+
+impl<'a> Resolver<'a> {
+  /// Unqualified references are resolved against the templates of the given repository
+  pub fn new(config: &'a Config, repository: &str, templates: &'a [Template]) -> Resolver<'a> {
+    Resolver {
+      config,
+      repository: utils::lowercase(repository),
+      templates,
+      others: HashMap::new(),
+    }
+  }
+
+  /// Linearize the extend graph, super templates come before the templates extending them and appear only once.
+  /// All cycles and dangling references are reported together.
+  pub fn resolve(&mut self, template: &Template) -> Result<Vec<Template>, RunError> {
+    let mut state = State::default();
+    let repository = self.repository.clone();
+
+    self.visit(&repository, template, &mut state);
+
+    if !state.errors.is_empty() {
+      return Err(RunError::Template(format!(
+        "Invalid extend of {}\n{}",
+        template.name,
+        state.errors.join("\n")
+      )));
+    }
+
+    Ok(state.templates)
+  }
+
+  fn visit(&mut self, repository: &str, template: &Template, state: &mut State) {
+    let meta_path = template.path.join("meta.json");
+    state.path.push(qualify(repository, &template.name));
+
+    let references = template.get_super_templates().unwrap_or_default();
+    for reference in references {
+      let (other, name) = repository::parse_reference(&reference);
+      let other = other.map(utils::lowercase).unwrap_or_else(|| repository.to_owned());
+      let key = qualify(&other, name);
+
+      if let Some(index) = state.path.iter().position(|item| *item == key) {
+        state.errors.push(format!(
+          "Circular extend {} -> {} in {}",
+          state.path[index..].join(" -> "),
+          key,
+          meta_path.to_string_lossy()
+        ));
+        continue;
+      }
+
+      // Templates reachable on multiple paths are only added once
+      if state.resolved.contains(&key) {
+        continue;
+      }
+
+      let super_template = match self.find(&other, name) {
+        Ok(super_template) => super_template,
+        Err(error) => {
+          state.errors.push(format!(
+            "Unable to resolve {} extended by {} in {}: {}",
+            reference,
+            template.name,
+            meta_path.to_string_lossy(),
+            error
+          ));
+          continue;
+        }
+      };
+
+      self.visit(&other, &super_template, state);
+
+      state.resolved.insert(key);
+      state.templates.push(super_template);
+    }
+
+    state.path.pop();
+  }
+
+  /// Find the template in the repository, other repositories are loaded once through the config
+  fn find(&mut self, repository: &str, name: &str) -> Result<Template, String> {
+    let name = utils::lowercase(name);
+
+    if repository == self.repository {
+      return match self.templates.iter().find(|template| template.name == name) {
+        Some(template) => Ok(template.to_owned()),
+        None => Err(String::from("Template not found")),
+      };
+    }
+
+    let config = self.config;
+    let other = self
+      .others
+      .entry(repository.to_owned())
+      .or_insert_with(|| repository::load(config, repository).map_err(|error| error.to_string()));
+
+    match other {
+      Ok(other) => other.get_template_by_name(&name).map(|template| template.to_owned()).map_err(|_| String::from("Template not found")),
+      Err(error) => Err(error.to_owned()),
+    }
+  }
+}
+
+fn qualify(repository: &str, name: &str) -> String {
+  format!("{}{}{}", repository, repository::REFERENCE_SEPARATOR, utils::lowercase(name))
+}
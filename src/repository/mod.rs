@@ -1,13 +1,16 @@
-use linked_hash_set::LinkedHashSet;
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
 
-use crate::config::{RepositoryOptions};
+use crate::config::{Config, RepositoryOptions};
 use crate::context::Context;
 use crate::error::RunError;
-use crate::meta::Value;
-use crate::template;
+use crate::template::{self, Template};
 use crate::renderer;
 
+use default_repository::DefaultRepository;
+use external_repository::ExternalRepository;
+use remote_repository::RemoteRepository;
+
+pub mod extend;
 pub mod remote_repository;
 pub mod default_repository;
 pub mod external_repository;
@@ -15,15 +18,88 @@ pub mod external_repository;
 pub trait Repository {
   fn get_config(&self) -> RepositoryOptions;
   fn copy_template(&self, ctx: &Context, opts: &CopyOptions) -> Result<(), RunError>;
-  fn get_template_info(&self, template_name: &str) -> Result<Option<String>, RunError>;
-  fn get_template_values(&self, template_name: &str) -> Result<LinkedHashSet<Value>, RunError>;
   fn get_template_names(&self) -> Vec<String>;
   fn get_template_by_name(&self, name: &str) -> Result<&template::Template, RunError>;
+  fn get_super_templates(&self, template_name: &str) -> Result<Vec<template::Template>, RunError>;
 }
 
 #[derive(Debug)]
 pub struct CopyOptions {
   pub template_name: String,
+  /// Addons of the template that are layered after it
+  pub addons: Vec<String>,
+  /// Templates already applied to the target that are not applied again
+  pub skip: Vec<PathBuf>,
   pub target: PathBuf,
   pub render_context: renderer::Context,
 }
+
+/// Separator of template references qualified with the repository like `platform:base-service`
+pub const REFERENCE_SEPARATOR: char = ':';
+
+/// Load the repository with the given name
+pub fn load(config: &Config, name: &str) -> Result<Box<dyn Repository>, RunError> {
+  if name == "templates" {
+    let repository = DefaultRepository::new(config)?;
+    return Ok(Box::new(repository));
+  }
+
+  let options = match config.get_repository_config(name) {
+    Some(options) => options,
+    None => return Err(RunError::Repository(String::from("Not found"))),
+  };
+
+  let repository: Box<dyn Repository> = if options.kind == Some(String::from("external")) {
+    Box::new(ExternalRepository::new(config, name)?)
+  } else {
+    Box::new(RemoteRepository::new(config, name)?)
+  };
+
+  Ok(repository)
+}
+
+/// Split a template reference into the optional repository and the template name
+pub fn parse_reference(reference: &str) -> (Option<&str>, &str) {
+  match reference.split_once(REFERENCE_SEPARATOR) {
+    Some((repository, name)) => (Some(repository), name),
+    None => (None, reference),
+  }
+}
+
+/// Templates initialized by `copy_template` in order: the extend chain and the template itself followed by
+/// the chosen addons with their extend chains. Templates reachable multiple times are only applied once and
+/// templates listed in `skip` are left out.
+pub fn get_layers(repository: &dyn Repository, opts: &CopyOptions) -> Result<Vec<Template>, RunError> {
+  let mut layers: Vec<Template> = Vec::new();
+
+  for name in std::iter::once(&opts.template_name).chain(opts.addons.iter()) {
+    let mut templates = repository.get_super_templates(name)?;
+    templates.push(repository.get_template_by_name(name)?.to_owned());
+
+    for template in templates {
+      if !opts.skip.contains(&template.path) && !layers.iter().any(|layer| layer.path == template.path) {
+        layers.push(template);
+      }
+    }
+  }
+
+  Ok(layers)
+}
+
+/// Register the partials of the repository and the layers, later layers have a higher priority.
+/// Layers of other repositories bring the partials of their repository.
+pub fn register_partials(renderer: &mut renderer::Renderer, directory: &Path, layers: &[Template]) -> Result<(), RunError> {
+  renderer.register_partials(&directory.join(template::PARTIALS_DIR))?;
+
+  for layer in layers {
+    if let Some(parent) = layer.path.parent() {
+      if parent != directory {
+        renderer.register_partials(&parent.join(template::PARTIALS_DIR))?;
+      }
+    }
+
+    renderer.register_partials(&layer.path.join(template::PARTIALS_DIR))?;
+  }
+
+  Ok(())
+}
@@ -1,5 +1,3 @@
-use linked_hash_set::LinkedHashSet;
-use std::collections::HashSet;
 use std::fs;
 use std::fs::File;
 use std::io::{Error, Write};
@@ -9,10 +7,10 @@ use crate::config::{Config, RepositoryOptions};
 use crate::context::Context;
 use crate::error::RunError;
 use crate::git;
-use crate::meta::{self, RepositoryMeta, TemplateMeta, Value};
-use crate::repository::{CopyOptions, Repository};
+use crate::meta::{self, RepositoryMeta, TemplateMeta};
+use crate::renderer;
+use crate::repository::{self, extend, CopyOptions, Repository};
 use crate::template;
-use crate::template::Template;
 use crate::utils;
 
 #[derive(Debug)]
@@ -21,6 +19,8 @@ pub struct RemoteRepository {
   pub directory: PathBuf,
   pub meta: Option<RepositoryMeta>,
   pub templates: Vec<template::Template>,
+  /// Used to resolve super templates of other repositories
+  pub global_config: Config,
 }
 
 impl Repository for RemoteRepository {
@@ -31,16 +31,16 @@ impl Repository for RemoteRepository {
   fn copy_template(&self, ctx: &Context, opts: &CopyOptions) -> Result<(), RunError> {
     let template = self.get_template_by_name(&opts.template_name)?;
 
-    let super_templates =
-      self.get_super_templates(template, &mut std::collections::HashSet::new())?;
+    let layers = repository::get_layers(self, opts)?;
 
-    // Initialize super templates
-    for template in super_templates.iter() {
-      template.init(ctx, &opts.target, &opts.render_context)?;
-    }
+    // One renderer is shared by all templates
+    let mut renderer = renderer::Renderer::new(&opts.render_context)?;
+    repository::register_partials(&mut renderer, &self.directory, &layers)?;
 
-    // Initialize template
-    template.init(ctx, &opts.target, &opts.render_context)?;
+    // Initialize super templates, the template and the addons
+    for layer in layers.iter() {
+      layer.init(ctx, &opts.target, &renderer)?;
+    }
 
     // Create info file
     template.create_info(&opts.target)?;
@@ -48,47 +48,6 @@ impl Repository for RemoteRepository {
     Ok(())
   }
 
-  fn get_template_info(&self, template_name: &str) -> Result<Option<String>, RunError> {
-    let template = self.get_template_by_name(template_name)?;
-
-    if template.meta.info.is_some() {
-      return Ok(template.meta.info.to_owned());
-    }
-
-    // Get list of all super templates
-    let super_templates = match self.get_super_templates(template, &mut HashSet::new()) {
-      Ok(templates) => templates,
-      Err(error) => return Err(error),
-    };
-
-    for template in super_templates.iter().rev() {
-      if template.meta.info.is_some() {
-        return Ok(template.meta.info.to_owned());
-      }
-    }
-
-    Ok(None)
-  }
-
-  fn get_template_values(&self, template_name: &str) -> Result<LinkedHashSet<Value>, RunError> {
-    let template = self.get_template_by_name(&template_name)?;
-
-    // Get list of all super templates
-    let super_templates = match self.get_super_templates(template, &mut HashSet::new()) {
-      Ok(templates) => templates,
-      Err(error) => return Err(error),
-    };
-
-    let mut values = LinkedHashSet::new();
-    for template in super_templates {
-      values.extend(template.meta.get_values());
-    }
-
-    values.extend(template.meta.get_values());
-
-    Ok(values)
-  }
-
   /// Return list of all template names in this repository
   fn get_template_names(&self) -> Vec<String> {
     let mut templates = Vec::<String>::new();
@@ -114,6 +73,13 @@ impl Repository for RemoteRepository {
 
     return Err(RunError::Template(String::from("Not found")));
   }
+
+  /// Return the linearized super templates of the template
+  fn get_super_templates(&self, template_name: &str) -> Result<Vec<template::Template>, RunError> {
+    let template = self.get_template_by_name(template_name)?;
+
+    extend::Resolver::new(&self.global_config, &self.config.name, &self.templates).resolve(template)
+  }
 }
 
 impl RemoteRepository {
@@ -133,6 +99,7 @@ impl RemoteRepository {
       directory: directory,
       meta: None,
       templates: Vec::<template::Template>::new(),
+      global_config: config.clone(),
     };
 
     // Ensure repository diectory
@@ -177,38 +144,6 @@ impl RemoteRepository {
     return Ok(());
   }
 
-  /// Get list of all super templates
-  fn get_super_templates(
-    &self,
-    template: &template::Template,
-    seen: &mut std::collections::HashSet<String>,
-  ) -> Result<Vec<Template>, RunError> {
-    // get list of all super templates
-    let super_templates = template.get_super_templates()?;
-
-    seen.insert(template.name.to_owned());
-
-    let mut templates = vec![];
-    for name in super_templates {
-      // Avoid circular dependencies;
-      if seen.contains(&name) {
-        continue;
-      }
-
-      let template = self.get_template_by_name(&name)?;
-
-      let t = match self.get_super_templates(template, seen) {
-        Ok(templates) => templates,
-        Err(error) => return Err(error),
-      };
-
-      templates.extend(t);
-      templates.push(template.to_owned());
-    }
-
-    Ok(templates)
-  }
-
   fn ensure_repository_dir(&self) -> Result<(), Error> {
     if !self.directory.exists() {
       match fs::create_dir(&self.directory) {
@@ -282,6 +217,11 @@ impl RemoteRepository {
         continue;
       }
 
+      // skip the shared partials of the repository
+      if entry.file_name() == template::PARTIALS_DIR {
+        continue;
+      }
+
       let meta = match meta::load::<TemplateMeta>(&entry.path()) {
         Ok(meta) => meta,
         Err(error) => {
@@ -318,6 +258,7 @@ pub fn add(config: &Config, options: &RepositoryOptions) -> Result<(), RunError>
     directory: directory,
     meta: None,
     templates: vec![],
+    global_config: config.clone(),
   };
 
   // Ensure repository diectory
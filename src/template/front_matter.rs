@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use crate::error::RunError;
+use crate::meta::MergeStrategy;
+
+extern crate serde;
+use regex::Regex;
+use serde::Deserialize;
+
+/// First line of a front matter header, the header is closed by a line containing `---`
+const OPENING: &str = "---tmpo";
+const CLOSING: &str = "---";
+
+/// Directives of a single template file, declared in a yaml header at the top of the file
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct FrontMatter {
+  /// Target path relative to the workspace root, the placeholders are rendered
+  pub to: Option<String>,
+  /// Expression that needs to be truthy to copy the file
+  pub condition: Option<String>,
+  /// Force or prevent rendering of the file content
+  pub render: Option<bool>,
+  pub merge: Option<MergeStrategy>,
+  pub inject: Option<Inject>,
+  /// Number of bytes of the header including the fences
+  #[serde(skip)]
+  pub length: usize,
+}
+
+/// Insert the content into an existing file instead of replacing it
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Inject {
+  /// Insert after the first line matching the regex
+  pub after: Option<String>,
+  /// Insert before the first line matching the regex
+  pub before: Option<String>,
+  /// Skip the injection if the existing file matches the regex
+  pub skip_if: Option<String>,
+}
+
+impl Inject {
+  /// Replace the placeholders of the patterns
+  pub fn render<F: Fn(&str) -> String>(&self, render: F) -> Inject {
+    Inject {
+      after: self.after.as_ref().map(|pattern| render(pattern)),
+      before: self.before.as_ref().map(|pattern| render(pattern)),
+      skip_if: self.skip_if.as_ref().map(|pattern| render(pattern)),
+    }
+  }
+
+  /// Insert the content into the existing text, returns None if the injection is skipped
+  pub fn apply(&self, existing: &str, content: &str) -> Result<Option<String>, String> {
+    if let Some(skip_if) = &self.skip_if {
+      if build_regex(skip_if)?.is_match(existing) {
+        return Ok(None);
+      }
+    }
+
+    let content = if content.ends_with('\n') { content.to_owned() } else { format!("{}\n", content) };
+
+    let (pattern, after) = match (&self.after, &self.before) {
+      (Some(pattern), _) => (pattern, true),
+      (None, Some(pattern)) => (pattern, false),
+      (None, None) => {
+        let separator = if existing.is_empty() || existing.ends_with('\n') { "" } else { "\n" };
+        return Ok(Some(format!("{}{}{}", existing, separator, content)));
+      }
+    };
+
+    let regex = build_regex(pattern)?;
+
+    let mut offset = 0;
+    for line in existing.split_inclusive('\n') {
+      if regex.is_match(line.trim_end_matches(&['\r', '\n'][..])) {
+        let index = if after { offset + line.len() } else { offset };
+        let separator = if after && !line.ends_with('\n') { "\n" } else { "" };
+
+        return Ok(Some(format!("{}{}{}{}", &existing[..index], separator, content, &existing[index..])));
+      }
+
+      offset += line.len();
+    }
+
+    Err(format!("No line matches the inject pattern {}", pattern))
+  }
+}
+
+/// Parse the front matter at the start of the file content, files without a header return None
+pub fn parse(data: &[u8], path: &Path) -> Result<Option<FrontMatter>, RunError> {
+  let mut lines = data.split_inclusive(|byte| *byte == b'\n');
+
+  // Binary files are not valid UTF-8 and never contain a header
+  let mut length = match lines.next().map(std::str::from_utf8) {
+    Some(Ok(line)) if line.trim_end() == OPENING => line.len(),
+    _ => return Ok(None),
+  };
+
+  let mut header = String::new();
+  loop {
+    let line = match lines.next() {
+      Some(line) => line,
+      None => {
+        return Err(RunError::Template(format!(
+          "Front matter of {} is not closed",
+          path.to_string_lossy()
+        )));
+      }
+    };
+    let line = match std::str::from_utf8(line) {
+      Ok(line) => line,
+      Err(_) => {
+        return Err(RunError::Template(format!(
+          "Front matter of {} is not valid UTF-8",
+          path.to_string_lossy()
+        )));
+      }
+    };
+
+    length += line.len();
+    if line.trim_end() == CLOSING {
+      break;
+    }
+
+    header.push_str(line);
+  }
+
+  let mut front_matter = if header.trim().is_empty() {
+    FrontMatter::default()
+  } else {
+    match serde_yaml::from_str::<FrontMatter>(&header) {
+      Ok(front_matter) => front_matter,
+      Err(error) => {
+        return Err(RunError::Template(format!(
+          "Invalid front matter in {}: {}",
+          path.to_string_lossy(),
+          error
+        )));
+      }
+    }
+  };
+  front_matter.length = length;
+
+  Ok(Some(front_matter))
+}
+
+fn build_regex(pattern: &str) -> Result<Regex, String> {
+  match Regex::new(pattern) {
+    Ok(regex) => Ok(regex),
+    Err(error) => Err(format!("Invalid inject pattern {}: {}", pattern, error)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let path = Path::new("main.rs");
+
+    let header = "---tmpo\nto: src/{{ name }}.rs\nmerge: append\n---\n";
+    let data = format!("{}fn main() {{}}\n", header);
+    let front_matter = parse(data.as_bytes(), path).unwrap().unwrap();
+    assert_eq!(front_matter.to.unwrap(), "src/{{ name }}.rs");
+    assert_eq!(front_matter.merge.unwrap(), MergeStrategy::Append);
+    assert_eq!(front_matter.length, header.len());
+
+    assert!(parse(b"---\nkey: value\n---\n", path).unwrap().is_none());
+    assert!(parse(b"\x89PNG\r\n\x1a\n", path).unwrap().is_none());
+    assert!(parse(b"---tmpo\nunknown: true\n---\n", path).is_err());
+    assert!(parse(b"---tmpo\nrender: false\n", path).is_err());
+  }
+
+  #[test]
+  fn test_inject() {
+    let existing = "mod a;\nmod b;\n\nfn main() {}";
+    let inject = Inject {
+      after: Some(String::from("^mod ")),
+      ..Inject::default()
+    };
+    assert_eq!(inject.apply(existing, "mod c;").unwrap().unwrap(), "mod a;\nmod c;\nmod b;\n\nfn main() {}");
+
+    let inject = Inject {
+      before: Some(String::from("^fn main")),
+      skip_if: Some(String::from("mod c;")),
+      ..Inject::default()
+    };
+    assert_eq!(inject.apply(existing, "mod c;\n").unwrap().unwrap(), "mod a;\nmod b;\n\nmod c;\nfn main() {}");
+    assert!(inject.apply("mod c;\nfn main() {}", "mod c;").unwrap().is_none());
+
+    assert_eq!(Inject::default().apply(existing, "// end").unwrap().unwrap(), "mod a;\nmod b;\n\nfn main() {}\n// end\n");
+    assert!(Inject { after: Some(String::from("^use ")), ..Inject::default() }.apply(existing, "").is_err());
+  }
+}
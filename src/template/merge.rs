@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::meta::MergeStrategy;
+
+/// Combine the content of a file with the existing file in the workspace
+pub fn merge(existing: &str, content: &str, strategy: &MergeStrategy, path: &Path) -> Result<String, String> {
+  match strategy {
+    MergeStrategy::Replace => Ok(content.to_owned()),
+    MergeStrategy::Append => Ok(append(existing, content)),
+    MergeStrategy::Union => Ok(union(existing, content)),
+    MergeStrategy::Merge => deep_merge(existing, content, path),
+  }
+}
+
+fn append(existing: &str, content: &str) -> String {
+  if existing.is_empty() || existing.ends_with('\n') {
+    return format!("{}{}", existing, content);
+  }
+
+  format!("{}\n{}", existing, content)
+}
+
+/// Append all lines that are not part of the existing file yet
+fn union(existing: &str, content: &str) -> String {
+  let mut seen: HashSet<&str> = existing.lines().map(|line| line.trim_end()).collect();
+
+  let mut result = existing.to_owned();
+  for line in content.lines() {
+    let key = line.trim_end();
+    if !key.is_empty() && !seen.insert(key) {
+      continue;
+    }
+
+    if !result.is_empty() && !result.ends_with('\n') {
+      result.push('\n');
+    }
+    result.push_str(line);
+    result.push('\n');
+  }
+
+  result
+}
+
+/// Merge the documents recursively, the format is detected by the file extension.
+/// Maps are merged key by key, lists get the missing items appended and other values are replaced.
+/// Comments of the existing file are not kept.
+fn deep_merge(existing: &str, content: &str, path: &Path) -> Result<String, String> {
+  let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase());
+
+  let mut result = match extension.as_deref() {
+    Some("json") => {
+      let mut base: serde_json::Value = serde_json::from_str(existing).map_err(|error| error.to_string())?;
+      let other: serde_json::Value = serde_json::from_str(content).map_err(|error| error.to_string())?;
+      merge_json(&mut base, other);
+
+      serde_json::to_string_pretty(&base).map_err(|error| error.to_string())?
+    }
+    Some("yaml") | Some("yml") => {
+      let mut base: serde_yaml::Value = serde_yaml::from_str(existing).map_err(|error| error.to_string())?;
+      let other: serde_yaml::Value = serde_yaml::from_str(content).map_err(|error| error.to_string())?;
+      merge_yaml(&mut base, other);
+
+      let data = serde_yaml::to_string(&base).map_err(|error| error.to_string())?;
+      if existing.starts_with("---") {
+        data
+      } else {
+        data.trim_start_matches("---\n").to_owned()
+      }
+    }
+    Some("toml") => {
+      let mut base: toml::Value = toml::from_str(existing).map_err(|error| error.to_string())?;
+      let other: toml::Value = toml::from_str(content).map_err(|error| error.to_string())?;
+      merge_toml(&mut base, other);
+
+      toml::to_string(&base).map_err(|error| error.to_string())?
+    }
+    _ => return Err(format!("Deep merge is not supported for {}", path.to_string_lossy())),
+  };
+
+  if !result.ends_with('\n') {
+    result.push('\n');
+  }
+
+  Ok(result)
+}
+
+fn merge_json(base: &mut serde_json::Value, other: serde_json::Value) {
+  match (base, other) {
+    (serde_json::Value::Object(base), serde_json::Value::Object(other)) => {
+      for (key, value) in other {
+        match base.get_mut(&key) {
+          Some(existing) => merge_json(existing, value),
+          None => {
+            base.insert(key, value);
+          }
+        }
+      }
+    }
+    (serde_json::Value::Array(base), serde_json::Value::Array(other)) => {
+      for value in other {
+        if !base.contains(&value) {
+          base.push(value);
+        }
+      }
+    }
+    (base, other) => *base = other,
+  }
+}
+
+fn merge_yaml(base: &mut serde_yaml::Value, other: serde_yaml::Value) {
+  match (base, other) {
+    (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(other)) => {
+      for (key, value) in other {
+        match base.get_mut(&key) {
+          Some(existing) => merge_yaml(existing, value),
+          None => {
+            base.insert(key, value);
+          }
+        }
+      }
+    }
+    (serde_yaml::Value::Sequence(base), serde_yaml::Value::Sequence(other)) => {
+      for value in other {
+        if !base.contains(&value) {
+          base.push(value);
+        }
+      }
+    }
+    (base, other) => *base = other,
+  }
+}
+
+fn merge_toml(base: &mut toml::Value, other: toml::Value) {
+  match (base, other) {
+    (toml::Value::Table(base), toml::Value::Table(other)) => {
+      for (key, value) in other {
+        match base.get_mut(&key) {
+          Some(existing) => merge_toml(existing, value),
+          None => {
+            base.insert(key, value);
+          }
+        }
+      }
+    }
+    (toml::Value::Array(base), toml::Value::Array(other)) => {
+      for value in other {
+        if !base.contains(&value) {
+          base.push(value);
+        }
+      }
+    }
+    (base, other) => *base = other,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_merge_lines() {
+    let path = Path::new(".gitignore");
+    assert_eq!(merge("target\n", "node_modules\n", &MergeStrategy::Replace, path).unwrap(), "node_modules\n");
+    assert_eq!(merge("target", "node_modules\n", &MergeStrategy::Append, path).unwrap(), "target\nnode_modules\n");
+    assert_eq!(
+      merge("target\n.env\n", "node_modules\ntarget\n\n.env\n", &MergeStrategy::Union, path).unwrap(),
+      "target\n.env\nnode_modules\n\n"
+    );
+  }
+
+  #[test]
+  fn test_merge_documents() {
+    let existing = r#"{ "name": "base", "scripts": { "build": "tsc" }, "files": ["dist"] }"#;
+    let content = r#"{ "scripts": { "test": "jest" }, "files": ["dist", "types"], "private": true }"#;
+    assert_eq!(
+      merge(existing, content, &MergeStrategy::Merge, Path::new("package.json")).unwrap(),
+      "{\n  \"name\": \"base\",\n  \"scripts\": {\n    \"build\": \"tsc\",\n    \"test\": \"jest\"\n  },\n  \"files\": [\n    \"dist\",\n    \"types\"\n  ],\n  \"private\": true\n}\n"
+    );
+
+    let existing = "services:\n  app:\n    image: base\n";
+    let content = "services:\n  app:\n    ports: [80]\n  db:\n    image: postgres\n";
+    assert_eq!(
+      merge(existing, content, &MergeStrategy::Merge, Path::new("compose.yml")).unwrap(),
+      "services:\n  app:\n    image: base\n    ports:\n      - 80\n  db:\n    image: postgres\n"
+    );
+
+    let existing = "[package]\nname = \"base\"\n\n[dependencies]\nserde = \"1.0\"\n";
+    let content = "[package]\nedition = \"2018\"\n\n[dependencies]\nlog = \"0.4\"\n";
+    assert_eq!(
+      merge(existing, content, &MergeStrategy::Merge, Path::new("Cargo.toml")).unwrap(),
+      "[package]\nname = \"base\"\nedition = \"2018\"\n\n[dependencies]\nserde = \"1.0\"\nlog = \"0.4\"\n"
+    );
+
+    assert!(merge("a", "b", &MergeStrategy::Merge, Path::new("readme.md")).is_err());
+    assert!(merge("{", "{}", &MergeStrategy::Merge, Path::new("package.json")).is_err());
+  }
+}
@@ -1,20 +1,34 @@
 use log;
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fs;
 use std::fs::File;
-use std::io::{ErrorKind, Read, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use crate::context::Context;
 use crate::error::RunError;
-use crate::{meta, {meta::TemplateMeta}};
+use crate::{meta, {meta::{MergeStrategy, Script, TemplateMeta}}};
+use crate::out;
 use crate::renderer;
 use crate::utils;
 
 extern crate serde;
 use serde::Serialize;
-
+extern crate ignore;
+use ignore::gitignore::Gitignore;
+extern crate rayon;
+use rayon::prelude::*;
+
+mod front_matter;
+mod merge;
+mod pattern;
 mod script;
 
+/// Directory of a template or repository that contains the handlebars partials
+pub const PARTIALS_DIR: &str = "_partials";
+
 #[derive(Serialize, Debug)]
 pub struct Info {
   name: String,
@@ -26,6 +40,63 @@ pub struct Template {
   pub name: String,
   pub path: PathBuf,
   pub meta: meta::TemplateMeta,
+  conditions: Vec<Condition>,
+  copy_exclude: Gitignore,
+  renderer_exclude: Gitignore,
+  renderer_include: Gitignore,
+  delimiters: Vec<DelimiterRule>,
+  repeats: Vec<Repeat>,
+  merges: Vec<MergeRule>,
+}
+
+/// Handlebars expression that needs to be truthy to copy the matched entries
+#[derive(Debug, Clone)]
+struct Condition {
+  matcher: Gitignore,
+  expression: String,
+}
+
+/// List expression the matched entries are copied for once per element
+#[derive(Debug, Clone)]
+struct Repeat {
+  pattern: String,
+  matcher: Gitignore,
+  expression: String,
+}
+
+/// File collected by `copy_folder`, the content is rendered and written afterwards
+#[derive(Debug)]
+struct PendingFile {
+  source: PathBuf,
+  target: PathBuf,
+  /// Content of the source including the front matter
+  data: Vec<u8>,
+  item: Option<renderer::Item>,
+  header: Option<front_matter::FrontMatter>,
+  merge: MergeStrategy,
+}
+
+impl PendingFile {
+  /// Check if the file needs to be combined with an existing file in the workspace
+  fn is_combined(&self) -> bool {
+    self.merge != MergeStrategy::Replace || self.header.as_ref().is_some_and(|header| header.inject.is_some())
+  }
+}
+
+/// Strategy used to combine the matched files with files of a previous template
+#[derive(Debug, Clone)]
+struct MergeRule {
+  pattern: String,
+  matcher: Gitignore,
+  strategy: MergeStrategy,
+}
+
+/// Delimiters used to render the matched entries
+#[derive(Debug, Clone)]
+struct DelimiterRule {
+  pattern: String,
+  matcher: Gitignore,
+  delimiters: renderer::Delimiters,
 }
 
 impl Template {
@@ -46,11 +117,78 @@ impl Template {
 
     let name = meta.name.to_owned();
 
+    let mut conditions = Vec::new();
+    if let Some(items) = &meta.conditions {
+      for (pattern, expression) in items {
+        conditions.push(Condition {
+          matcher: pattern::build(dir, &[pattern.to_owned()])?,
+          expression: expression.to_owned(),
+        });
+      }
+    }
+
+    // Exclude patterns from meta and .tmpoignore, files needed by tmpo itself are always excluded
+    let mut copy_patterns = match &meta.exclude {
+      Some(items) => items.clone(),
+      None => Vec::new(),
+    };
+    copy_patterns.extend(load_ignore_file(dir)?);
+    copy_patterns.extend(vec![
+      String::from("/meta.json"),
+      String::from("/.tmpoignore"),
+      format!("/{}/", PARTIALS_DIR),
+      String::from(".git"),
+    ]);
+    let copy_exclude = pattern::build(dir, &copy_patterns)?;
+
+    let renderer_patterns = match meta.renderer.as_ref().and_then(|renderer| renderer.exclude.as_ref()) {
+      Some(items) => items.clone(),
+      None => Vec::new(),
+    };
+    let renderer_exclude = pattern::build(dir, &renderer_patterns)?;
+
+    let renderer_patterns = match meta.renderer.as_ref().and_then(|renderer| renderer.include.as_ref()) {
+      Some(items) => items.clone(),
+      None => Vec::new(),
+    };
+    let renderer_include = pattern::build(dir, &renderer_patterns)?;
+
+    let delimiters = load_delimiters(dir, &meta)?;
+
+    let mut repeats = Vec::new();
+    if let Some(items) = meta.renderer.as_ref().and_then(|renderer| renderer.repeat.as_ref()) {
+      for (pattern, expression) in items {
+        repeats.push(Repeat {
+          pattern: pattern.to_owned(),
+          matcher: pattern::build(dir, &[pattern.to_owned()])?,
+          expression: expression.to_owned(),
+        });
+      }
+    }
+
+    let mut merges = Vec::new();
+    if let Some(items) = &meta.merge {
+      for (pattern, strategy) in items {
+        merges.push(MergeRule {
+          pattern: pattern.to_owned(),
+          matcher: pattern::build(dir, &[pattern.to_owned()])?,
+          strategy: strategy.clone(),
+        });
+      }
+    }
+
     // make all names lowercase
     return Ok(Template {
       name: utils::lowercase(&name),
       path: dir.to_path_buf(),
-      meta: meta,
+      meta,
+      conditions,
+      copy_exclude,
+      renderer_exclude,
+      renderer_include,
+      delimiters,
+      repeats,
+      merges,
     });
   }
 
@@ -69,41 +207,151 @@ impl Template {
     &self,
     ctx: &Context,
     target: &Path,
-    opts: &renderer::Context,
+    renderer: &renderer::Renderer,
   ) -> Result<(), RunError> {
     log::info!("Initialize Template: {}", self.name);
 
+    let generation = self.generation(ctx, target, renderer);
+
     // Run before install script if not disabled
-    if !ctx.no_script {
-      let before_install_script = self.meta.get_before_install_script();
-      if before_install_script.is_some() {
-        let script = renderer::render(&before_install_script.unwrap(), &opts);
+    if ctx.is_script_enabled(&self.path) {
+      let scripts = self.prepare_scripts(self.meta.get_before_install_scripts(), &generation)?;
+      run_scripts(ctx, &scripts, &generation)?;
+    }
 
-        script::run(&script, target);
-      }
+    // Create directories and links, files are collected and copied afterwards
+    let mut files = Vec::new();
+    self.copy_folder(&self.path, &target, &generation, None, &mut files)?;
+
+    // Entries rendered to the same path would overwrite each other in an arbitrary order
+    let mut targets = HashSet::new();
+    if let Some(file) = files.iter().find(|file| !targets.insert(&file.target)) {
+      return Err(RunError::Template(format!(
+        "Entry {} is rendered to {} more than once",
+        file.source.to_string_lossy(),
+        file.target.to_string_lossy()
+      )));
+    }
+
+    // Render and write the files in parallel, files combined with existing ones are written in order afterwards
+    let (combined, files): (Vec<PendingFile>, Vec<PendingFile>) = files
+      .into_iter()
+      .partition(|file| file.is_combined());
+    files.par_iter().try_for_each(|file| self.copy_file(file, &generation))?;
+    for file in combined.iter() {
+      self.copy_file(file, &generation)?;
+    }
+
+    // Remove directories of the template that are left empty because their files were moved
+    remove_vacated_dirs(&generation.created_dirs.lock().unwrap(), &generation.vacated_dirs.lock().unwrap())?;
+
+    // Remove directories of the template that only contained dropped or excluded entries
+    if self.is_remove_empty() {
+      prune_empty_dirs(&generation.created_dirs.lock().unwrap())?;
     }
 
-    // Copy files
-    self.copy_folder(&self.path, &target, &opts)?;
+    // Abort before any script is executed
+    generation.check()?;
 
     // Run after install script if not disabled
-    if !ctx.no_script {
-      let after_install_script = self.meta.get_after_install_script();
-      if after_install_script.is_some() {
-        let script = renderer::render(&after_install_script.unwrap(), &opts);
+    if ctx.is_script_enabled(&self.path) {
+      let scripts = self.prepare_scripts(self.meta.get_after_install_scripts(), &generation)?;
+      run_scripts(ctx, &scripts, &generation)?;
+    }
+
+    Ok(())
+  }
+
+  /// Rendered scripts of the template that run on this system, together with the hash of their definition.
+  /// The values are the declarations of all layers, the ones the scripts use are part of the hash
+  pub fn get_scripts(
+    &self,
+    ctx: &Context,
+    target: &Path,
+    renderer: &renderer::Renderer,
+    values: &[meta::Value],
+  ) -> Result<Vec<(String, Script)>, RunError> {
+    let generation = self.generation(ctx, target, renderer);
+
+    let mut scripts = self.prepare_scripts(self.meta.get_before_install_scripts(), &generation)?;
+    scripts.extend(self.prepare_scripts(self.meta.get_after_install_scripts(), &generation)?);
+
+    let scripts = scripts
+      .into_iter()
+      .map(|(script, rendered)| (script::hash(&script, renderer.get_partials(), values), rendered))
+      .collect();
+
+    Ok(scripts)
+  }
+
+  fn generation<'a>(&'a self, ctx: &Context, target: &'a Path, renderer: &'a renderer::Renderer) -> Generation<'a> {
+    Generation {
+      renderer,
+      workspace: target,
+      delimiters: &self.delimiters,
+      default_delimiters: renderer::Delimiters::default(),
+      engine: self.get_engine(),
+      strict: ctx.strict || self.is_strict(),
+      issues: Mutex::new(Vec::new()),
+      created_dirs: Mutex::new(Vec::new()),
+      vacated_dirs: Mutex::new(Vec::new()),
+    }
+  }
+
+  /// Render the steps of a script, steps for other systems or with unfulfilled conditions are skipped
+  /// Every script is returned with its definition and the rendered script
+  fn prepare_scripts(&self, scripts: Vec<Script>, generation: &Generation) -> Result<Vec<(Script, Script)>, RunError> {
+    let meta_path = self.path.join("meta.json");
+
+    let mut prepared = Vec::new();
+    for script in scripts.into_iter().filter(|script| script.is_supported()) {
+      if !script.has_command() {
+        return Err(RunError::Template(format!("Script {} of {} has neither run nor args", script.get_name(), self.name)));
+      }
 
-        script::run(&script, target);
+      if let Some(when) = &script.when {
+        if !generation.evaluate(when, None) {
+          log::info!("Skip script {}: condition {} not fulfilled", script.get_name(), when);
+          continue;
+        }
       }
+
+      let rendered = script.render(|text| generation.render(text, &meta_path, None));
+      prepared.push((script, rendered));
     }
+    generation.check()?;
 
-    Ok(())
+    Ok(prepared)
+  }
+
+  pub fn get_engine(&self) -> meta::Engine {
+    match self.meta.renderer.as_ref().and_then(|renderer| renderer.engine.as_ref()) {
+      Some(engine) => engine.clone(),
+      None => meta::Engine::default(),
+    }
+  }
+
+  fn is_remove_empty(&self) -> bool {
+    match &self.meta.renderer {
+      Some(renderer) => renderer.remove_empty.unwrap_or(false),
+      None => false,
+    }
+  }
+
+  fn is_strict(&self) -> bool {
+    match &self.meta.renderer {
+      Some(renderer) => renderer.strict.unwrap_or(false),
+      None => false,
+    }
   }
 
   fn copy_folder(
     &self,
     src: &Path,
     target: &Path,
-    opts: &renderer::Context,
+    generation: &Generation,
+    item: Option<&renderer::Item>,
+    files: &mut Vec<PendingFile>,
   ) -> Result<(), RunError> {
     // Loop at selected template directory
     let entries = match fs::read_dir(src) {
@@ -115,98 +363,320 @@ impl Template {
       let entry = &entry.unwrap();
 
       let source_path = &entry.path();
-      let source_name = &entry
-        .path()
-        .file_name()
-        .unwrap()
-        .to_string_lossy()
-        .into_owned();
-
-      let mut path = target.to_path_buf();
-      path.push(source_name);
-
-      // Replace placeholders in path
-      path = PathBuf::from(renderer::render(&path.to_string_lossy(), &opts));
-
-      // Check if entry is directory
-      if entry.path().is_dir() {
-        match fs::create_dir(&path) {
-          Ok(()) => (),
-          Err(error) => match error.kind() {
-            ErrorKind::AlreadyExists => (),
-            _ => return Err(RunError::IO(error)),
-          },
-        };
-
-        // TODO
-        if self.is_excluded_copy(&source_name) {
-          continue;
-        }
+      let file_type = entry.file_type()?;
+      let is_dir = file_type.is_dir();
 
-        self.copy_folder(&source_path, &path, opts)?
-      } else {
-        if self.is_excluded_copy(&source_name) {
-          continue;
+      if self.is_excluded_copy(source_path, is_dir) {
+        continue;
+      }
+
+      // Repeated entries are copied once for every element of the list
+      let items = match self.get_repeat(source_path, is_dir) {
+        Some(expression) => {
+          let count = generation.renderer.resolve_list(expression, &generation.engine)?.len();
+          (0..count)
+            .map(|index| Some(renderer::Item { expression: expression.to_owned(), index }))
+            .collect()
         }
+        None => vec![item.cloned()],
+      };
 
-        // Open file
-        let mut src = File::open(Path::new(&source_path))?;
-        let mut data = String::new();
+      for item in items.iter() {
+        self.copy_entry(entry, target, generation, item.as_ref(), files)?;
+      }
+    }
 
-        // Write to data string
-        src.read_to_string(&mut data)?;
+    Ok(())
+  }
 
-        // Close file
-        drop(src);
+  fn copy_entry(
+    &self,
+    entry: &fs::DirEntry,
+    target: &Path,
+    generation: &Generation,
+    item: Option<&renderer::Item>,
+    files: &mut Vec<PendingFile>,
+  ) -> Result<(), RunError> {
+    let source_path = &entry.path();
+    let source_name = &entry
+      .path()
+      .file_name()
+      .unwrap()
+      .to_string_lossy()
+      .into_owned();
+    let file_type = entry.file_type()?;
+
+    if !self.is_included(source_path, file_type.is_dir(), generation, item) {
+      return Ok(());
+    }
 
-        // Replace placeholders in data
-        // Skip if file should be excluded
-        if !self.is_excluded_renderer(&source_name) {
-          data = renderer::render(&data, &opts);
-        }
+    // Files are read once, the front matter is taken from the loaded content.
+    // Directives of the front matter only apply to the file itself
+    let data = if file_type.is_file() { fs::read(source_path)? } else { Vec::new() };
+    let header = if file_type.is_file() { front_matter::parse(&data, source_path)? } else { None };
+    if let Some(condition) = header.as_ref().and_then(|header| header.condition.as_ref()) {
+      if !generation.evaluate(condition, item) {
+        log::info!("Skip {}: condition {} not fulfilled", source_path.to_string_lossy(), condition);
+        return Ok(());
+      }
+    }
+
+    // Replace placeholders in the name, a rendered name can contain multiple directories.
+    // A target path of the front matter is relative to the workspace root
+    let (root, name) = match header.as_ref().and_then(|header| header.to.as_ref()) {
+      Some(to) => {
+        generation.vacated_dirs.lock().unwrap().push(target.to_owned());
+        (generation.workspace, generation.render(to, source_path, item))
+      }
+      None => (target, generation.render(source_name, source_path, item)),
+    };
+    let relative_path = match utils::normalize_relative_path(Path::new(&name)) {
+      Some(path) => path,
+      None => {
+        return Err(RunError::Template(format!(
+          "Rendered path {} of {} leaves the workspace",
+          name,
+          source_path.to_string_lossy()
+        )));
+      }
+    };
 
-        // Create file
-        let mut dst = File::create(path)?;
-        dst.write(data.as_bytes())?;
+    if relative_path.as_os_str().is_empty() {
+      log::info!("Skip {}: rendered name is empty", source_path.to_string_lossy());
+      return Ok(());
+    }
 
-        // Close file
-        drop(dst);
+    let path = root.join(&relative_path);
+    check_links(generation.workspace, &path, file_type.is_dir())?;
+
+    // Check if entry is a symbolic link, links are recreated instead of followed
+    if file_type.is_symlink() {
+      create_parent(root, &path)?;
+      self.copy_symlink(source_path, &path, generation, item)?;
+    } else if file_type.is_dir() {
+      // Directories of previous templates are never pruned
+      if !path.exists() {
+        fs::create_dir_all(&path)?;
+        generation.created_dirs.lock().unwrap().push(path.to_owned());
       }
+
+      self.copy_folder(source_path, &path, generation, item, files)?
+    } else {
+      create_parent(root, &path)?;
+      // The strategy of the front matter takes precedence over the patterns of the meta
+      let merge = match header.as_ref().and_then(|header| header.merge.as_ref()) {
+        Some(merge) => merge.clone(),
+        None => self.get_merge_strategy(source_path),
+      };
+
+      files.push(PendingFile {
+        source: source_path.to_owned(),
+        target: path,
+        data,
+        item: item.cloned(),
+        header,
+        merge,
+      });
     }
 
     Ok(())
   }
 
-  fn is_excluded_copy(&self, name: &str) -> bool {
-    if name == "meta.json" || name == ".git" {
-      return true;
+  fn copy_file(&self, file: &PendingFile, generation: &Generation) -> Result<(), RunError> {
+    let source = &file.source;
+    let target = &file.target;
+    let header = file.header.as_ref();
+
+    // Strip the front matter
+    let mut data = match header {
+      Some(header) => Cow::Borrowed(&file.data[header.length..]),
+      None => Cow::Borrowed(&file.data[..]),
     };
 
-    let items = match &self.meta.exclude {
-      None => return false,
-      Some(x) => x,
+    // Replace placeholders in data
+    let rendered = match header.and_then(|header| header.render) {
+      Some(rendered) => rendered,
+      None => self.is_rendered(source, &data),
     };
+    if rendered {
+      let text = String::from_utf8_lossy(&data).into_owned();
+      data = Cow::Owned(generation.render(&text, source, file.item.as_ref()).into_bytes());
+
+      // Drop files whose content is fully removed by the renderer, blank files like .gitkeep are kept
+      if self.is_remove_empty() && is_blank(&data) && !text.trim().is_empty() {
+        log::info!("Skip {}: rendered content is empty", source.to_string_lossy());
+        return Ok(());
+      }
+    }
+
+    // Don't write through a link created by a previous template
+    check_links(generation.workspace, target, false)?;
+    remove_symlink(target)?;
+
+    // Combine the file with the existing file of a previous template
+    let exists = target.exists();
+    if exists && file.is_combined() {
+      let existing = fs::read_to_string(target)?;
+      let content = String::from_utf8_lossy(&data);
+
+      let combined = match header.and_then(|header| header.inject.as_ref()) {
+        Some(inject) => match inject
+          .render(|pattern| generation.render(pattern, source, file.item.as_ref()))
+          .apply(&existing, &content)
+        {
+          Ok(combined) => combined,
+          Err(error) => {
+            return Err(RunError::Template(format!("Unable to inject {}: {}", source.to_string_lossy(), error)));
+          }
+        },
+        None => match merge::merge(&existing, &content, &file.merge, target) {
+          Ok(merged) => Some(merged),
+          Err(error) => {
+            return Err(RunError::Template(format!("Unable to merge {}: {}", source.to_string_lossy(), error)));
+          }
+        },
+      };
+
+      match combined {
+        Some(combined) => {
+          fs::write(target, combined)?;
+          copy_permissions(source, target)?;
+        }
+        None => log::info!("Skip {}: already injected", source.to_string_lossy()),
+      }
+
+      return Ok(());
+    }
+
+    if !exists && header.is_some_and(|header| header.inject.is_some()) {
+      return Err(RunError::Template(format!(
+        "Unable to inject {}: {} does not exist",
+        source.to_string_lossy(),
+        target.to_string_lossy()
+      )));
+    }
+
+    fs::write(target, data)?;
+    copy_permissions(source, target)?;
+
+    Ok(())
+  }
+
+  fn copy_symlink(
+    &self,
+    source: &Path,
+    target: &Path,
+    generation: &Generation,
+    item: Option<&renderer::Item>,
+  ) -> Result<(), RunError> {
+    let mut link = PathBuf::from(generation.render(&fs::read_link(source)?.to_string_lossy(), source, item));
+
+    // Links pointing into the template are made relative so they point into the workspace
+    let root = fs::canonicalize(&self.path)?;
+    let parent = fs::canonicalize(source.parent().unwrap())?;
+    if link.is_absolute() && link.starts_with(&root) {
+      link = utils::relative_path(&parent, &link);
+    }
+
+    // Only links resolving inside the template and the workspace are kept
+    let source_dir = utils::relative_path(&root, &parent);
+    let target_dir = target.parent().and_then(|dir| dir.strip_prefix(generation.workspace).ok());
+    let is_inside = |dir: &Path| utils::resolve_relative_path(&dir.join(&link)).is_some();
+    if !is_inside(&source_dir) || !target_dir.is_some_and(is_inside) {
+      log::warn!("Skip {}: link {} points outside of the template", source.to_string_lossy(), link.to_string_lossy());
+      out::warn::symlink_skipped(&source.to_string_lossy(), &link.to_string_lossy());
+      return Ok(());
+    }
+
+    remove_symlink(target)?;
 
-    items.contains(&name.to_owned())
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&link, target)?;
+    #[cfg(windows)]
+    {
+      if source.is_dir() {
+        std::os::windows::fs::symlink_dir(&link, target)?;
+      } else {
+        std::os::windows::fs::symlink_file(&link, target)?;
+      }
+    }
+
+    Ok(())
   }
 
-  fn is_excluded_renderer(&self, name: &str) -> bool {
-    if self.meta.renderer.is_none() {
+  /// Render files that are detected as text or explicitly included
+  fn is_rendered(&self, path: &Path, data: &[u8]) -> bool {
+    if self.is_excluded_renderer(path) {
       return false;
     }
 
-    let items = match &self.meta.renderer.as_ref().unwrap().exclude {
-      None => return false,
-      Some(x) => x,
-    };
+    if pattern::is_match(&self.renderer_include, path, false) {
+      return true;
+    }
+
+    is_text(data)
+  }
+
+  /// Check if all conditions matching the entry are fulfilled
+  fn is_included(&self, path: &Path, is_dir: bool, generation: &Generation, item: Option<&renderer::Item>) -> bool {
+    for condition in &self.conditions {
+      if !pattern::is_match(&condition.matcher, path, is_dir) {
+        continue;
+      }
+
+      if !generation.evaluate(&condition.expression, item) {
+        log::info!("Skip {}: condition {} not fulfilled", path.to_string_lossy(), condition.expression);
+        return false;
+      }
+    }
+
+    true
+  }
 
-    items.contains(&name.to_owned())
+  /// List expression of the most specific repeat pattern matching the entry.
+  /// The longest pattern is the most specific one, on a tie the later declaration wins.
+  fn get_repeat(&self, path: &Path, is_dir: bool) -> Option<&str> {
+    self
+      .repeats
+      .iter()
+      .filter(|repeat| pattern::is_match_exact(&repeat.matcher, path, is_dir))
+      .max_by_key(|repeat| repeat.pattern.len())
+      .map(|repeat| repeat.expression.as_str())
+  }
+
+  /// Merge strategy of the most specific pattern matching the file.
+  /// The longest pattern is the most specific one, on a tie the later declaration wins.
+  fn get_merge_strategy(&self, path: &Path) -> MergeStrategy {
+    let rule = self
+      .merges
+      .iter()
+      .filter(|rule| pattern::is_match(&rule.matcher, path, false))
+      .max_by_key(|rule| rule.pattern.len());
+
+    match rule {
+      Some(rule) => rule.strategy.clone(),
+      None => MergeStrategy::default(),
+    }
+  }
+
+  fn is_excluded_copy(&self, path: &Path, is_dir: bool) -> bool {
+    pattern::is_match(&self.copy_exclude, path, is_dir)
+  }
+
+  fn is_excluded_renderer(&self, path: &Path) -> bool {
+    pattern::is_match(&self.renderer_exclude, path, false)
   }
 
   pub fn create_info(&self, target: &Path) -> Result<(), std::io::Error> {
     // Create .tmpo.yaml file
     // Not used yet
     let info_path = &target.to_path_buf().join(".tmpo.yaml");
+
+    // The first template of a composed workspace describes it
+    if info_path.exists() {
+      return Ok(());
+    }
+
     let mut info_file = fs::File::create(info_path)?;
 
     // Fill meta
@@ -222,6 +692,272 @@ impl Template {
   }
 }
 
+/// Run the rendered steps of a script in order
+fn run_scripts(ctx: &Context, scripts: &[(Script, Script)], generation: &Generation) -> Result<(), RunError> {
+  if scripts.is_empty() {
+    return Ok(());
+  }
+
+  // Expose the render context to the scripts, the context file is removed after the last step
+  let context = generation.renderer.get_context();
+  let context_file = script::write_context(context)?;
+  let env = script::get_env(context, context_file.path(), generation.workspace);
+
+  for (index, (_, script)) in scripts.iter().enumerate() {
+    // The working directory is relative to the workspace root
+    let directory = match &script.cwd {
+      Some(cwd) => match utils::normalize_relative_path(Path::new(cwd)) {
+        Some(path) => generation.workspace.join(path),
+        None => {
+          return Err(RunError::Template(format!(
+            "Working directory {} of script {} leaves the workspace",
+            cwd,
+            script.get_name()
+          )));
+        }
+      },
+      None => generation.workspace.to_owned(),
+    };
+
+    out::info::script_step(index + 1, scripts.len(), &script.get_name());
+    script::run(ctx, script, &directory, &env)?;
+  }
+
+  Ok(())
+}
+
+/// Render state of a single template initialization
+struct Generation<'a> {
+  renderer: &'a renderer::Renderer,
+  /// Root directory of the workspace
+  workspace: &'a Path,
+  engine: meta::Engine,
+  delimiters: &'a [DelimiterRule],
+  default_delimiters: renderer::Delimiters,
+  strict: bool,
+  issues: Mutex<Vec<String>>,
+  /// Directories created for the template
+  created_dirs: Mutex<Vec<PathBuf>>,
+  /// Directories that files were moved out of by a target path of the front matter
+  vacated_dirs: Mutex<Vec<PathBuf>>,
+}
+
+impl Generation<'_> {
+  /// Render the text, in strict mode all issues are collected together with the source file
+  fn render(&self, text: &str, source: &Path, item: Option<&renderer::Item>) -> String {
+    let options = renderer::Options {
+      engine: &self.engine,
+      delimiters: self.get_delimiters(source),
+      item,
+    };
+
+    if !self.strict {
+      return self.renderer.render_with(text, &options);
+    }
+
+    match self.renderer.render_strict_with(text, &options) {
+      Ok(result) => result,
+      Err(issues) => {
+        let mut collected = self.issues.lock().unwrap();
+        for issue in issues {
+          let location = match issue.line {
+            Some(line) => format!("{}:{}", source.to_string_lossy(), line),
+            None => source.to_string_lossy().into_owned(),
+          };
+          collected.push(format!("{}: {}", location, issue.message));
+        }
+
+        text.to_owned()
+      }
+    }
+  }
+
+  /// Evaluate a condition, conditions always use the default delimiters
+  fn evaluate(&self, expression: &str, item: Option<&renderer::Item>) -> bool {
+    let options = renderer::Options {
+      engine: &self.engine,
+      delimiters: &self.default_delimiters,
+      item,
+    };
+
+    self.renderer.evaluate(expression, &options)
+  }
+
+  /// Delimiters of the most specific pattern matching the source.
+  /// The longest pattern is the most specific one, on a tie the later declaration wins.
+  fn get_delimiters(&self, source: &Path) -> &renderer::Delimiters {
+    let rule = self
+      .delimiters
+      .iter()
+      .filter(|rule| pattern::is_match(&rule.matcher, source, false))
+      .max_by_key(|rule| rule.pattern.len());
+
+    match rule {
+      Some(rule) => &rule.delimiters,
+      None => &self.default_delimiters,
+    }
+  }
+
+  /// Fail if missing values or other render issues were found
+  fn check(&self) -> Result<(), RunError> {
+    let mut issues = self.issues.lock().unwrap();
+    if issues.is_empty() {
+      return Ok(());
+    }
+
+    // Files are rendered in parallel, sort to get a stable report
+    issues.sort();
+
+    Err(RunError::Render(format!(
+      "{} render issues\n{}",
+      issues.len(),
+      issues.join("\n")
+    )))
+  }
+}
+
+/// Files are treated as text if they are valid UTF-8 and don't contain null bytes
+fn is_text(data: &[u8]) -> bool {
+  let sample = &data[..data.len().min(8000)];
+  if sample.contains(&0) {
+    return false;
+  }
+
+  std::str::from_utf8(data).is_ok()
+}
+
+fn is_blank(data: &[u8]) -> bool {
+  data.iter().all(|byte| byte.is_ascii_whitespace())
+}
+
+/// Remove the directories that are left empty, nested directories are removed before their parents
+fn prune_empty_dirs(dirs: &[PathBuf]) -> Result<(), std::io::Error> {
+  let mut dirs = dirs.to_vec();
+  dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+
+  for dir in dirs {
+    if fs::read_dir(&dir)?.next().is_none() {
+      log::info!("Remove empty directory {}", dir.to_string_lossy());
+      fs::remove_dir(&dir)?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Remove the created directories that are left empty because their entries were moved,
+/// parents only holding such directories are removed as well
+fn remove_vacated_dirs(created: &[PathBuf], vacated: &[PathBuf]) -> Result<(), std::io::Error> {
+  let mut dirs = created.to_vec();
+  dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+
+  let mut removed: Vec<PathBuf> = Vec::new();
+  for dir in dirs {
+    let is_vacated = vacated.contains(&dir) || removed.iter().any(|path| path.parent() == Some(dir.as_path()));
+    if is_vacated && fs::read_dir(&dir)?.next().is_none() {
+      log::info!("Remove vacated directory {}", dir.to_string_lossy());
+      fs::remove_dir(&dir)?;
+      removed.push(dir);
+    }
+  }
+
+  Ok(())
+}
+
+/// Create the directories of a rendered name that expands into several segments
+fn create_parent(target: &Path, path: &Path) -> Result<(), std::io::Error> {
+  match path.parent() {
+    Some(parent) if parent != target => fs::create_dir_all(parent),
+    _ => Ok(()),
+  }
+}
+
+/// Make sure no directory between the workspace root and the path is a symbolic link,
+/// writing through it could leave the workspace. Directories are checked themselves as well
+fn check_links(workspace: &Path, path: &Path, is_dir: bool) -> Result<(), RunError> {
+  let relative = match path.strip_prefix(workspace) {
+    Ok(relative) => relative,
+    Err(_) => return Err(RunError::Template(format!("Path {} leaves the workspace", path.to_string_lossy()))),
+  };
+
+  let mut dirs: Vec<&Path> = relative.ancestors().skip(if is_dir { 0 } else { 1 }).collect();
+  dirs.reverse();
+
+  for dir in dirs.into_iter().filter(|dir| !dir.as_os_str().is_empty()) {
+    match fs::symlink_metadata(workspace.join(dir)) {
+      Ok(metadata) if metadata.file_type().is_symlink() => {
+        return Err(RunError::Template(format!(
+          "Path {} goes through the link {}",
+          relative.to_string_lossy(),
+          dir.to_string_lossy()
+        )));
+      }
+      Ok(_) => (),
+      Err(_) => break,
+    }
+  }
+
+  Ok(())
+}
+
+/// Keep permissions of the source like the executable bit of scripts, the file stays writable
+fn copy_permissions(source: &Path, target: &Path) -> Result<(), std::io::Error> {
+  let mut permissions = fs::metadata(source)?.permissions();
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    permissions.set_mode(permissions.mode() | 0o200);
+  }
+  #[cfg(not(unix))]
+  permissions.set_readonly(false);
+
+  fs::set_permissions(target, permissions)
+}
+
+fn remove_symlink(path: &Path) -> Result<(), std::io::Error> {
+  match fs::symlink_metadata(path) {
+    Ok(metadata) if metadata.file_type().is_symlink() => fs::remove_file(path),
+    _ => Ok(()),
+  }
+}
+
+/// Build the delimiter rules of the renderer options, delimiters for the whole template match every entry
+fn load_delimiters(dir: &Path, meta: &TemplateMeta) -> Result<Vec<DelimiterRule>, RunError> {
+  let items = match meta.renderer.as_ref().and_then(|renderer| renderer.delimiters.as_ref()) {
+    Some(meta::DelimitersWrapper::Template(delimiters)) => vec![(String::from("*"), delimiters.clone())],
+    Some(meta::DelimitersWrapper::Patterns(patterns)) => patterns.clone().into_iter().collect(),
+    None => Vec::new(),
+  };
+
+  let mut rules = Vec::new();
+  for (pattern, (start, end)) in items {
+    if start.is_empty() || end.is_empty() || start == end {
+      return Err(RunError::Template(format!("Invalid delimiters {} {} for {}", start, end, pattern)));
+    }
+
+    rules.push(DelimiterRule {
+      matcher: pattern::build(dir, &[pattern.to_owned()])?,
+      pattern,
+      delimiters: renderer::Delimiters { start, end },
+    });
+  }
+
+  Ok(rules)
+}
+
+/// Load the exclude patterns of the optional .tmpoignore file in the template root
+fn load_ignore_file(dir: &Path) -> Result<Vec<String>, RunError> {
+  let ignore_path = dir.join(".tmpoignore");
+  if !ignore_path.exists() {
+    return Ok(Vec::new());
+  }
+
+  let data = fs::read_to_string(ignore_path)?;
+  let patterns = data.lines().map(|line| line.to_owned()).collect();
+
+  Ok(patterns)
+}
+
 /// Create a new template with given name in the repository directory
 pub fn create(dir: &Path, meta: &meta::TemplateMeta) -> Result<std::path::PathBuf, RunError> {
   let template_path = dir.join(utils::lowercase(&meta.name));
@@ -0,0 +1,91 @@
+use std::path::Path;
+
+use crate::error::RunError;
+
+extern crate ignore;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// Build a gitignore style matcher for the given patterns.
+/// Patterns are matched against the path relative to the root directory.
+pub fn build(root: &Path, patterns: &[String]) -> Result<Gitignore, RunError> {
+  let mut builder = GitignoreBuilder::new(root);
+
+  for pattern in patterns {
+    match builder.add_line(None, pattern) {
+      Ok(_) => (),
+      Err(error) => {
+        return Err(RunError::Template(format!("Invalid pattern {}: {}", pattern, error)));
+      }
+    };
+  }
+
+  match builder.build() {
+    Ok(matcher) => Ok(matcher),
+    Err(error) => Err(RunError::Template(format!("Invalid patterns: {}", error))),
+  }
+}
+
+/// Check if the path or one of its parents is matched by the patterns
+pub fn is_match(matcher: &Gitignore, path: &Path, is_dir: bool) -> bool {
+  if !path.starts_with(matcher.path()) {
+    return false;
+  }
+
+  matcher.matched_path_or_any_parents(path, is_dir).is_ignore()
+}
+
+/// Check if the path itself is matched by the patterns, parents are not considered
+pub fn is_match_exact(matcher: &Gitignore, path: &Path, is_dir: bool) -> bool {
+  if !path.starts_with(matcher.path()) {
+    return false;
+  }
+
+  matcher.matched(path, is_dir).is_ignore()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_match_file_name() -> Result<(), Box<dyn std::error::Error>> {
+    let root = Path::new("/template");
+    let matcher = build(root, &[String::from("Dockerfile")]).unwrap();
+
+    assert!(is_match(&matcher, &root.join("Dockerfile"), false));
+    assert!(is_match(&matcher, &root.join("service/Dockerfile"), false));
+    assert!(!is_match(&matcher, &root.join("Dockerfile.dev"), false));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_match_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let root = Path::new("/template");
+    let matcher = build(root, &[String::from("/ci/")]).unwrap();
+
+    assert!(is_match(&matcher, &root.join("ci"), true));
+    assert!(is_match(&matcher, &root.join("ci/gitlab.yml"), false));
+    assert!(!is_match(&matcher, &root.join("src/ci"), true));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_match_negation() -> Result<(), Box<dyn std::error::Error>> {
+    let root = Path::new("/template");
+    let patterns = vec![
+      String::from("docs/*.md"),
+      String::from("!docs/keep.md"),
+      String::from("assets/**"),
+    ];
+    let matcher = build(root, &patterns).unwrap();
+
+    assert!(is_match(&matcher, &root.join("docs/readme.md"), false));
+    assert!(!is_match(&matcher, &root.join("docs/keep.md"), false));
+    assert!(!is_match(&matcher, &root.join("readme.md"), false));
+    assert!(is_match(&matcher, &root.join("assets/images/logo.png"), false));
+
+    Ok(())
+  }
+}
